@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+/// One row of the game's ITEM.json
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CatalogItem {
+    pub item_id: String,
+    pub name: Option<String>,
+    pub grade: Option<i64>,
+    pub tier: Option<i64>,
+    pub main_type: Option<i64>,
+    pub sub_type: Option<i64>,
+    pub trade_type: Option<i64>,
+}
+
+/// Reads the game's ITEM.json
+pub fn read_items(items_path: &Path) -> anyhow::Result<Value> {
+    let data = fs::read_to_string(items_path)?;
+
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// Maps every item id of the game's ITEM.json to its TradeType, the format of list.json
+pub fn trade_types(items: &Value) -> HashMap<&String, Option<i64>> {
+    let mut trade_types = HashMap::new();
+    if let Value::Object(obj) = &items[0]["Rows"] {
+        for (item_id, item_object) in obj {
+            trade_types.insert(item_id, integer(&item_object["TradeType"]));
+        }
+    }
+
+    trade_types
+}
+
+/// A number of ITEM.json, numbers are sometimes exported as strings
+pub fn integer(value: &Value) -> Option<i64> {
+    value
+        .as_i64()
+        .or_else(|| value.as_str().and_then(|text| text.parse().ok()))
+}
+
+/// Every row of the game's ITEM.json, ordered by item id
+pub fn item_catalog(items: &Value) -> Vec<CatalogItem> {
+    let mut catalog = Vec::new();
+    if let Value::Object(obj) = &items[0]["Rows"] {
+        for (item_id, item_object) in obj {
+            catalog.push(CatalogItem {
+                item_id: item_id.clone(),
+                name: item_object["Name"].as_str().map(str::to_string),
+                grade: integer(&item_object["Grade"]),
+                tier: integer(&item_object["Tier"]),
+                main_type: integer(&item_object["MainType"]),
+                sub_type: integer(&item_object["SubType"]),
+                trade_type: integer(&item_object["TradeType"]),
+            });
+        }
+    }
+
+    catalog.sort_by(|a, b| a.item_id.cmp(&b.item_id));
+    catalog
+}
+
+/// Writes list.json to `output_path`
+pub fn write_trade_types(items: &Value, output_path: &Path) -> anyhow::Result<()> {
+    let new_json = json!(trade_types(items));
+    fs::write(output_path, serde_json::to_string_pretty(&new_json)?)?;
+
+    Ok(())
+}
+
+/// Writes the item catalog to `output_path` as a JSON array
+pub fn write_item_catalog(catalog: &[CatalogItem], output_path: &Path) -> anyhow::Result<()> {
+    fs::write(output_path, serde_json::to_string_pretty(catalog)?)?;
+
+    Ok(())
+}
+
+
+/// Reads an item catalog from the game's ITEM.json, or from a catalog written by `write_item_catalog`
+pub fn read_catalog(path: &Path) -> anyhow::Result<Vec<CatalogItem>> {
+    let items = read_items(path)?;
+    if items[0].get("Rows").is_some() {
+        return Ok(item_catalog(&items));
+    }
+
+    Ok(serde_json::from_value(items)?)
+}
+
+/// An item whose field changed between two catalogs
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ItemChange<T> {
+    pub item_id: String,
+    pub name: Option<String>,
+    pub before: T,
+    pub after: T,
+}
+
+/// What changed between two versions of the item catalog
+#[derive(Serialize, Debug, Default)]
+pub struct CatalogDiff {
+    pub added: Vec<CatalogItem>,
+    pub removed: Vec<CatalogItem>,
+    pub trade_type_changed: Vec<ItemChange<Option<i64>>>,
+    pub renamed: Vec<ItemChange<Option<String>>>,
+}
+
+/// Compares two catalogs, every list is ordered by item id
+pub fn diff_catalogs(old: &[CatalogItem], new: &[CatalogItem]) -> CatalogDiff {
+    let old_items: BTreeMap<&String, &CatalogItem> =
+        old.iter().map(|item| (&item.item_id, item)).collect();
+    let new_items: BTreeMap<&String, &CatalogItem> =
+        new.iter().map(|item| (&item.item_id, item)).collect();
+
+    let mut diff = CatalogDiff::default();
+    for (item_id, old_item) in &old_items {
+        let Some(new_item) = new_items.get(item_id) else {
+            diff.removed.push((*old_item).clone());
+            continue;
+        };
+
+        if old_item.trade_type != new_item.trade_type {
+            diff.trade_type_changed.push(ItemChange {
+                item_id: item_id.to_string(),
+                name: new_item.name.clone(),
+                before: old_item.trade_type,
+                after: new_item.trade_type,
+            });
+        }
+        if old_item.name != new_item.name {
+            diff.renamed.push(ItemChange {
+                item_id: item_id.to_string(),
+                name: new_item.name.clone(),
+                before: old_item.name.clone(),
+                after: new_item.name.clone(),
+            });
+        }
+    }
+
+    for (item_id, new_item) in &new_items {
+        if !old_items.contains_key(item_id) {
+            diff.added.push((*new_item).clone());
+        }
+    }
+
+    diff
+}
+
+fn trade_label(trade_type: Option<i64>) -> String {
+    match trade_type {
+        Some(0) => "not tradable".to_string(),
+        Some(1) => "tradable".to_string(),
+        Some(trade_type) => format!("trade type {}", trade_type),
+        None => "no trade type".to_string(),
+    }
+}
+
+fn name_label(name: &Option<String>) -> &str {
+    name.as_deref().unwrap_or("(no name)")
+}
+
+impl CatalogDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.trade_type_changed.is_empty()
+            && self.renamed.is_empty()
+    }
+
+    /// Human readable changelog, one line per item
+    pub fn changelog(&self) -> String {
+        if self.is_empty() {
+            return "No item changed\n".to_string();
+        }
+
+        let mut lines = Vec::new();
+        if !self.added.is_empty() {
+            lines.push(format!("Added ({})", self.added.len()));
+            for item in &self.added {
+                lines.push(format!(
+                    "  + {} {} ({})",
+                    item.item_id,
+                    name_label(&item.name),
+                    trade_label(item.trade_type)
+                ));
+            }
+        }
+        if !self.removed.is_empty() {
+            lines.push(format!("Removed ({})", self.removed.len()));
+            for item in &self.removed {
+                lines.push(format!(
+                    "  - {} {} ({})",
+                    item.item_id,
+                    name_label(&item.name),
+                    trade_label(item.trade_type)
+                ));
+            }
+        }
+        if !self.trade_type_changed.is_empty() {
+            lines.push(format!("Trade type changed ({})", self.trade_type_changed.len()));
+            for change in &self.trade_type_changed {
+                lines.push(format!(
+                    "  ~ {} {}: {} -> {}",
+                    change.item_id,
+                    name_label(&change.name),
+                    trade_label(change.before),
+                    trade_label(change.after)
+                ));
+            }
+        }
+        if !self.renamed.is_empty() {
+            lines.push(format!("Renamed ({})", self.renamed.len()));
+            for change in &self.renamed {
+                lines.push(format!(
+                    "  ~ {}: {} -> {}",
+                    change.item_id,
+                    name_label(&change.before),
+                    name_label(&change.after)
+                ));
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(item_id: &str, name: &str, trade_type: Option<i64>) -> CatalogItem {
+        CatalogItem {
+            item_id: item_id.to_string(),
+            name: Some(name.to_string()),
+            grade: None,
+            tier: None,
+            main_type: None,
+            sub_type: None,
+            trade_type,
+        }
+    }
+
+    fn ids(items: &[CatalogItem]) -> Vec<&str> {
+        items.iter().map(|item| item.item_id.as_str()).collect()
+    }
+
+    #[test]
+    fn diff_catalogs_finds_every_kind_of_change() {
+        let old = [
+            item("1", "Dragon Scale", Some(1)),
+            item("2", "Old Sword", Some(1)),
+            item("3", "Moonlight Bracelet", Some(0)),
+            item("4", "Potion", Some(0)),
+        ];
+        let new = [
+            item("1", "Dragon Scale", Some(0)),
+            item("3", "Moonlit Bracelet", Some(0)),
+            item("4", "Potion", Some(0)),
+            item("5", "New Ring", Some(1)),
+        ];
+
+        let diff = diff_catalogs(&old, &new);
+
+        assert_eq!(ids(&diff.added), ["5"]);
+        assert_eq!(ids(&diff.removed), ["2"]);
+        assert_eq!(
+            diff.trade_type_changed,
+            [ItemChange {
+                item_id: "1".to_string(),
+                name: Some("Dragon Scale".to_string()),
+                before: Some(1),
+                after: Some(0),
+            }]
+        );
+        assert_eq!(
+            diff.renamed,
+            [ItemChange {
+                item_id: "3".to_string(),
+                name: Some("Moonlit Bracelet".to_string()),
+                before: Some("Moonlight Bracelet".to_string()),
+                after: Some("Moonlit Bracelet".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn trade_types_reads_strings_as_numbers() {
+        let items = json!([{"Rows": {
+            "100": {"TradeType": 1},
+            "200": {"TradeType": "0"},
+            "300": {"Name": "No trade type"}
+        }}]);
+        let trade_types = trade_types(&items);
+
+        assert_eq!(trade_types[&"100".to_string()], Some(1));
+        assert_eq!(trade_types[&"200".to_string()], Some(0));
+        assert_eq!(trade_types[&"300".to_string()], None);
+    }
+
+    #[test]
+    fn diff_catalogs_of_the_same_catalog_is_empty() {
+        let catalog = [item("1", "Dragon Scale", Some(1)), item("2", "Potion", None)];
+
+        assert!(diff_catalogs(&catalog, &catalog).is_empty());
+    }
+}
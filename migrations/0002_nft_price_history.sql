@@ -0,0 +1,13 @@
+alter table public.nft add column if not exists price_updated_at timestamp with time zone not null default now();
+
+create table if not exists
+  public.nft_price_history (
+    id bigint generated by default as identity,
+    transport_id bigint not null,
+    seq integer not null,
+    price integer not null,
+    observed_at timestamp with time zone not null default now(),
+    constraint nft_price_history_pkey primary key (id)
+  ) tablespace pg_default;
+
+create index if not exists nft_price_history_transport_id_idx on public.nft_price_history using btree (transport_id, observed_at) tablespace pg_default;
@@ -0,0 +1,5 @@
+alter table public.nft add column if not exists status text not null default 'listed'::text;
+alter table public.nft add column if not exists listed_at timestamp with time zone not null default now();
+alter table public.nft add column if not exists delisted_at timestamp with time zone null;
+
+create index if not exists nft_status_idx on public.nft using btree (status) tablespace pg_default;
@@ -0,0 +1,9 @@
+create table if not exists
+  public.localized_name (
+    kind text not null,
+    key text not null,
+    language_code text not null,
+    name text not null,
+    updated_at timestamp with time zone not null default now(),
+    constraint localized_name_pkey primary key (kind, key, language_code)
+  ) tablespace pg_default;
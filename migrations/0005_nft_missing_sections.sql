@@ -0,0 +1 @@
+alter table public.nft add column if not exists missing_sections text[] not null default '{}'::text[];
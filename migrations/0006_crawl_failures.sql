@@ -0,0 +1,14 @@
+create table if not exists
+  public.crawl_failures (
+    id bigint generated by default as identity,
+    transport_id bigint not null,
+    seq integer not null,
+    section text not null,
+    error text not null,
+    payload text null,
+    listing jsonb not null,
+    attempts integer not null default 1,
+    failed_at timestamp with time zone not null default now(),
+    constraint crawl_failures_pkey primary key (id),
+    constraint crawl_failures_transport_id_section_key unique (transport_id, section)
+  ) tablespace pg_default;
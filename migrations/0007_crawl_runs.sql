@@ -0,0 +1,17 @@
+create table if not exists
+  public.crawl_runs (
+    id bigint generated by default as identity,
+    started_at timestamp with time zone not null default now(),
+    finished_at timestamp with time zone null,
+    initial_page integer not null,
+    final_page integer null,
+    pages_fetched integer not null default 0,
+    failed_pages integer not null default 0,
+    new_characters integer not null default 0,
+    updated_characters integer not null default 0,
+    skipped_characters integer not null default 0,
+    failed_characters integer not null default 0,
+    requests jsonb not null default '{}'::jsonb,
+    duration_ms bigint null,
+    constraint crawl_runs_pkey primary key (id)
+  ) tablespace pg_default;
@@ -0,0 +1,11 @@
+create table if not exists
+  public.item_catalog (
+    item_id text not null,
+    name text null,
+    grade integer null,
+    tier integer null,
+    main_type integer null,
+    sub_type integer null,
+    trade_type integer null,
+    constraint item_catalog_pkey primary key (item_id)
+  ) tablespace pg_default;
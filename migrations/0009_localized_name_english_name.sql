@@ -0,0 +1,3 @@
+-- rows are keyed by stable ids instead of english names, the old ones are fetched again with the next new characters
+delete from public.localized_name where kind <> 'item';
+alter table public.localized_name add column if not exists english_name text null;
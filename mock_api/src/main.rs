@@ -0,0 +1,75 @@
+use axum::{
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use clap::Parser;
+use serde::Deserialize;
+
+/// Mock of the Mir4 NFT web API serving bundled fixtures
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// Port to listen on
+    #[arg(short, long, env = "MOCK_API_PORT", default_value_t = 3030)]
+    port: u16,
+}
+
+#[derive(Deserialize, Debug)]
+struct ListQuery {
+    page: Option<u32>,
+}
+
+const EMPTY_LIST: &str = r#"{"code":200,"data":{"firstID":0,"totalCount":0,"more":0,"lists":[]}}"#;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+
+    let app = Router::new()
+        .route("/nft/lists", get(lists))
+        .route("/nft/character/:endpoint", get(character));
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", cli.port)).await?;
+    println!("Mock Mir4 API listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, app).await
+}
+
+async fn lists(Query(query): Query<ListQuery>) -> Response {
+    // every fixture character fits in the first page, the rest of the listing is empty
+    match query.page.unwrap_or(1) {
+        1 => json(include_str!("../fixtures/lists.json")),
+        _ => json(EMPTY_LIST),
+    }
+}
+
+async fn character(Path(endpoint): Path<String>) -> Response {
+    let fixture = match endpoint.as_str() {
+        "inven" => include_str!("../fixtures/inven.json"),
+        "summary" => include_str!("../fixtures/summary.json"),
+        "itemdetail" => include_str!("../fixtures/itemdetail.json"),
+        "succession" => include_str!("../fixtures/succession.json"),
+        "magicstone" => include_str!("../fixtures/magicstone.json"),
+        "mysticalpiece" => include_str!("../fixtures/mysticalpiece.json"),
+        "spirit" => include_str!("../fixtures/spirit.json"),
+        "magicorb" => include_str!("../fixtures/magicorb.json"),
+        "stats" => include_str!("../fixtures/stats.json"),
+        "skills" => include_str!("../fixtures/skills.json"),
+        "training" => include_str!("../fixtures/training.json"),
+        "building" => include_str!("../fixtures/building.json"),
+        "assets" => include_str!("../fixtures/assets.json"),
+        "potential" => include_str!("../fixtures/potential.json"),
+        "holystuff" => include_str!("../fixtures/holystuff.json"),
+        "codex" => include_str!("../fixtures/codex.json"),
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+
+    json(fixture)
+}
+
+fn json(body: &'static str) -> Response {
+    ([(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
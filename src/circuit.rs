@@ -0,0 +1,278 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+struct BreakerState {
+    consecutive_failures: u32,
+    /// Set while paused, requests are held until then
+    open_until: Option<Instant>,
+    /// A single request is testing the API after a pause, the others wait for its answer
+    probing: bool,
+}
+
+enum Admission {
+    Send,
+    Probe,
+    WaitUntil(Instant),
+    WaitForProbe,
+}
+
+/// Pauses every request to the Mir4 API for `cooldown` once `threshold` requests in a row failed
+/// (network errors, timeouts and 5xx), e.g. during server maintenance. After the pause a single
+/// request is sent, the others resume when it succeeds and wait for another pause when it fails.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Arc<Mutex<BreakerState>>,
+    probed: Arc<Notify>,
+}
+
+impl CircuitBreaker {
+    /// A `threshold` of 0 never pauses
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold,
+            cooldown,
+            state: Arc::new(Mutex::new(BreakerState {
+                consecutive_failures: 0,
+                open_until: None,
+                probing: false,
+            })),
+            probed: Arc::new(Notify::new()),
+        }
+    }
+
+    fn admit(&self) -> Admission {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            Some(open_until) if open_until > Instant::now() => Admission::WaitUntil(open_until),
+            Some(_) if state.probing => Admission::WaitForProbe,
+            Some(_) => {
+                state.probing = true;
+                Admission::Probe
+            }
+            None => Admission::Send,
+        }
+    }
+
+    fn record(&self, probe: bool, failed: bool) {
+        let mut state = self.state.lock().unwrap();
+        if probe {
+            state.probing = false;
+            if failed {
+                tracing::warn!("Mir4 API is still failing, pausing requests for {:?}", self.cooldown);
+                state.open_until = Some(Instant::now() + self.cooldown);
+            } else {
+                tracing::info!("Mir4 API is answering again, resuming requests");
+                state.open_until = None;
+                state.consecutive_failures = 0;
+            }
+            self.probed.notify_waiters();
+            return;
+        }
+
+        // sent before the pause started
+        if state.open_until.is_some() {
+            return;
+        }
+
+        if !failed {
+            state.consecutive_failures = 0;
+            return;
+        }
+
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.threshold {
+            tracing::warn!(
+                "Mir4 API failed {} times in a row, pausing requests for {:?}",
+                state.consecutive_failures,
+                self.cooldown
+            );
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+/// Lets another request probe when the probe is dropped before its answer, e.g. by a timeout,
+/// otherwise `probing` stays set and every later request waits for it forever
+struct ProbeGuard<'a> {
+    breaker: &'a CircuitBreaker,
+    answered: bool,
+}
+
+impl Drop for ProbeGuard<'_> {
+    fn drop(&mut self) {
+        if !self.answered {
+            self.breaker.state.lock().unwrap().probing = false;
+            self.breaker.probed.notify_waiters();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for CircuitBreaker {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        if self.threshold == 0 {
+            return next.run(req, extensions).await;
+        }
+
+        let probe = loop {
+            // registered before looking at the state, so the probe answer can not be missed
+            let probed = self.probed.notified();
+            match self.admit() {
+                Admission::Send => break false,
+                Admission::Probe => break true,
+                Admission::WaitUntil(open_until) => tokio::time::sleep_until(open_until).await,
+                Admission::WaitForProbe => probed.await,
+            }
+        };
+
+        let mut probe_guard = probe.then_some(ProbeGuard {
+            breaker: self,
+            answered: false,
+        });
+
+        let result = next.run(req, extensions).await;
+        let failed = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(_) => true,
+        };
+        self.record(probe, failed);
+        if let Some(probe_guard) = probe_guard.as_mut() {
+            probe_guard.answered = true;
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+    use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    /// Answers with the next status, `None` never answers
+    struct Answers(Mutex<Vec<Option<StatusCode>>>);
+
+    #[async_trait::async_trait]
+    impl Middleware for Answers {
+        async fn handle(
+            &self,
+            _req: Request,
+            _extensions: &mut Extensions,
+            _next: Next<'_>,
+        ) -> reqwest_middleware::Result<Response> {
+            let status = self.0.lock().unwrap().remove(0);
+            match status {
+                Some(status) => {
+                    let mut response = http::Response::new("");
+                    *response.status_mut() = status;
+                    Ok(Response::from(response))
+                }
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    fn client(breaker: &CircuitBreaker, answers: Vec<Option<StatusCode>>) -> ClientWithMiddleware {
+        ClientBuilder::new(reqwest::Client::new())
+            .with(breaker.clone())
+            .with(Answers(Mutex::new(answers)))
+            .build()
+    }
+
+    fn open_breaker() -> CircuitBreaker {
+        let breaker = CircuitBreaker::new(2, COOLDOWN);
+        breaker.record(false, true);
+        breaker.record(false, true);
+        breaker
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_threshold_failures_in_a_row() {
+        let breaker = CircuitBreaker::new(2, COOLDOWN);
+        breaker.record(false, true);
+        breaker.record(false, false);
+        breaker.record(false, true);
+        assert!(matches!(breaker.admit(), Admission::Send));
+
+        breaker.record(false, true);
+        assert!(matches!(
+            breaker.admit(),
+            Admission::WaitUntil(open_until) if open_until == Instant::now() + COOLDOWN
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sends_a_single_probe_after_the_cooldown() {
+        let breaker = open_breaker();
+        tokio::time::advance(COOLDOWN).await;
+
+        assert!(matches!(breaker.admit(), Admission::Probe));
+        assert!(matches!(breaker.admit(), Admission::WaitForProbe));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resumes_when_the_probe_succeeds() {
+        let breaker = open_breaker();
+        tokio::time::advance(COOLDOWN).await;
+        breaker.admit();
+
+        breaker.record(true, false);
+        assert!(matches!(breaker.admit(), Admission::Send));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pauses_again_when_the_probe_fails() {
+        let breaker = open_breaker();
+        tokio::time::advance(COOLDOWN).await;
+        breaker.admit();
+
+        breaker.record(true, true);
+        assert!(matches!(breaker.admit(), Admission::WaitUntil(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn holds_requests_until_the_probe_answers() {
+        let breaker = open_breaker();
+        let client = client(&breaker, vec![Some(StatusCode::OK), Some(StatusCode::OK)]);
+
+        let started = Instant::now();
+        let (first, second) = tokio::join!(
+            client.get("http://mir4.test/").send(),
+            client.get("http://mir4.test/").send()
+        );
+
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        assert_eq!(second.unwrap().status(), StatusCode::OK);
+        assert_eq!(started.elapsed(), COOLDOWN);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_dropped_probe_lets_another_request_probe() {
+        let breaker = open_breaker();
+        let client = client(&breaker, vec![None, Some(StatusCode::OK)]);
+        tokio::time::advance(COOLDOWN).await;
+
+        let probe = tokio::time::timeout(Duration::from_secs(1), client.get("http://mir4.test/").send());
+        assert!(probe.await.is_err());
+
+        let next = tokio::time::timeout(Duration::from_secs(1), client.get("http://mir4.test/").send());
+        assert_eq!(next.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert!(matches!(breaker.admit(), Admission::Send));
+    }
+}
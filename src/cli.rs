@@ -1,22 +1,457 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use crate::utils::DEFAULT_API_BASE_URL;
 
 /// Backend for https://www.mir4scope.com
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
+    /// Load environment variables from a .env file [default: false]
+    #[arg(short, long, global = true, default_value_t = false)]
+    pub local: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Crawl the market listing into the database
+    Crawl(CrawlArgs),
+    /// Serve the stored NFT data over HTTP
+    Serve {
+        /// Port used by the HTTP server
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Apply the pending database migrations, they are also applied before every other command
+    Migrate,
+    /// Crawl again the characters recorded in crawl_failures
+    RetryFailures {
+        #[command(flatten)]
+        api: ApiOptions,
+    },
+    /// Crawl a single character and print it as JSON
+    Fetch(FetchArgs),
+    /// Export the stored characters flattened to one column per stat
+    Export(ExportArgs),
+    /// List the most recent crawl runs
+    Runs {
+        /// Amount of runs listed
+        #[arg(default_value_t = 10)]
+        count: u32,
+    },
+    /// Build the tradable item list and the item catalog from the game's ITEM.json
+    Catalog(CatalogArgs),
+    /// Compare two versions of the item catalog
+    CatalogDiff {
+        /// Previous ITEM.json, or a catalog written by catalog --catalog
+        old: PathBuf,
+
+        /// New ITEM.json, or a catalog written by catalog --catalog
+        new: PathBuf,
+
+        /// Also write the differences as JSON to FILE
+        #[arg(long, value_name = "FILE")]
+        json: Option<PathBuf>,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct CatalogArgs {
+    /// ITEM.json extracted from the game data
+    #[arg(long, default_value = "/ITEM.json")]
+    pub items: PathBuf,
+
+    /// Where the tradable item list is written
+    #[arg(long, default_value = "list.json")]
+    pub output: PathBuf,
+
+    /// Also write every item with its name, grade, tier, types and trade type to FILE
+    #[arg(long, value_name = "FILE")]
+    pub catalog: Option<PathBuf>,
+
+    /// Replace the item_catalog table with every item [default: false]
+    #[arg(long, default_value_t = false)]
+    pub load: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct CrawlArgs {
+    /// If the backend should drop the database or not. [Default: false]
+    #[arg(short, long, default_value_t = false)]
+    pub db_drop: bool,
+
+    /// Write every crawled character as a JSON line to FILE (- for stdout) instead of the database
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["db_drop", "languages"])]
+    pub ndjson: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub api: ApiOptions,
+
+    #[command(flatten)]
+    pub daemon: DaemonOptions,
+
+    #[command(flatten)]
+    pub crawl: CrawlOptions,
+}
+
+#[derive(Args, Debug)]
+#[command(group = clap::ArgGroup::new("character").required(true))]
+pub struct FetchArgs {
+    /// Transport ID of the character
+    #[arg(long, group = "character")]
+    pub transport_id: Option<i32>,
+
+    /// Listing seq of the character
+    #[arg(long, group = "character")]
+    pub seq: Option<i32>,
+
+    /// Store the character, replacing the stored copy [default: false]
+    #[arg(long, default_value_t = false)]
+    pub save: bool,
+
+    /// Maximum amount of listing pages searched for the character
+    #[arg(long, default_value_t = 20)]
+    pub max_pages: u32,
+
+    #[command(flatten)]
+    pub api: ApiOptions,
+
+    #[command(flatten)]
+    pub filter: ListingFilter,
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+    pub format: ExportFormat,
+
+    /// Where the export is written, - for stdout
+    #[arg(long, value_name = "FILE", default_value = "-")]
+    pub output: PathBuf,
+
+    #[command(flatten)]
+    pub filter: ExportFilter,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Parquet,
+}
+
+/// Filters of the exported characters, every one is optional
+#[derive(Args, Debug)]
+pub struct ExportFilter {
+    /// Character class
+    #[arg(long)]
+    pub class: Option<i16>,
+
+    /// Minimum character level
+    #[arg(long)]
+    pub lev_min: Option<i16>,
+
+    /// Maximum character level
+    #[arg(long)]
+    pub lev_max: Option<i16>,
+
+    /// World name, e.g. ASIA011
+    #[arg(long)]
+    pub world: Option<String>,
+}
+
+/// Where characters are fetched from and how they are enriched
+#[derive(Args, Debug, Clone)]
+pub struct ApiOptions {
+    /// Base URL of the Mir4 API, point it to the mock server to crawl offline
+    #[arg(long, env = "MIR4_API_URL", default_value = DEFAULT_API_BASE_URL)]
+    pub api_url: String,
+
+    /// Write every raw API response to this directory, keyed by endpoint and query
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Serve API responses previously written with --record instead of hitting the network
+    #[arg(long, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// Tradable item list built by the catalog command
+    #[arg(long, value_name = "FILE", default_value = "list.json")]
+    pub list: PathBuf,
+
+    /// Maximum amount of requests to the Mir4 API in flight at the same time
+    #[arg(long, default_value_t = 16)]
+    pub max_in_flight: usize,
+
+    /// Maximum amount of requests sent to the Mir4 API per second, 0 for no limit. Halved every
+    /// time the API answers 429 or 5xx, it recovers slowly afterwards
+    #[arg(long, default_value_t = 10)]
+    pub requests_per_second: u32,
+
+    /// Retries of a request that failed with a network error or a 5xx
+    #[arg(long, default_value_t = 1)]
+    pub retries: u32,
+
+    /// Seconds waited before the first retry, doubled on every following one
+    #[arg(long, default_value_t = 1)]
+    pub retry_min_delay: u64,
+
+    /// Maximum seconds waited between retries
+    #[arg(long, default_value_t = 30)]
+    pub retry_max_delay: u64,
+
+    /// Seconds before a request is given up, 0 for no timeout
+    #[arg(long, default_value_t = 30)]
+    pub timeout: u64,
+
+    /// Verify the TLS certificate of the Mir4 API, by default invalid certificates are accepted
+    /// [default: false]
+    #[arg(long, default_value_t = false)]
+    pub verify_certs: bool,
+
+    /// Failed requests in a row that pause every request for --breaker-cooldown, 0 never pauses
+    #[arg(long, default_value_t = 20)]
+    pub breaker_threshold: u32,
+
+    /// Seconds requests are paused once --breaker-threshold requests failed in a row
+    #[arg(long, default_value_t = 60)]
+    pub breaker_cooldown: u64,
+
+    /// Maximum amount of item details requested at the same time
+    #[arg(long, default_value_t = 8)]
+    pub item_detail_concurrency: usize,
+
+    /// Languages to crawl localized names in, besides english (e.g. pt,es)
+    #[arg(long, value_delimiter = ',')]
+    pub languages: Vec<String>,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct CrawlOptions {
+    #[command(flatten)]
+    pub pages: PageOptions,
+
+    #[command(flatten)]
+    pub filter: ListingFilter,
+
+    /// Crawl the market split by class and power band instead of as a single listing [default: false]
+    #[arg(long, default_value_t = false, conflicts_with_all = ["class", "power_min", "power_max"])]
+    pub partition: bool,
+
+    /// Classes crawled by --partition
+    #[arg(long, value_delimiter = ',', default_value = "1,2,3,4,5,6,7")]
+    pub partition_classes: Vec<u8>,
+
+    /// Lower bounds of the power bands crawled by --partition, the last band has no upper bound
+    #[arg(long, value_delimiter = ',', default_value = "0")]
+    pub power_bands: Vec<u32>,
+}
+
+impl CrawlOptions {
+    /// Whether the crawl sees every listed character, only then a missing seq means delisted
+    pub fn covers_whole_market(&self) -> bool {
+        // characters on the skipped first pages are listed too
+        if self.pages.initial_page != 1 || !self.filter.is_unrestricted() {
+            return false;
+        }
+
+        // partitions cover the whole market with every class and a lowest band starting at 0
+        !self.partition
+            || ((1..=7).all(|class| self.partition_classes.contains(&class))
+                && self.power_bands.iter().min() == Some(&0))
+    }
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct DaemonOptions {
+    /// Keep running and crawl on a schedule instead of crawling once [default: false]
+    #[arg(long, default_value_t = false)]
+    pub daemon: bool,
+
+    /// Seconds between refreshes of the first pages of the listing
+    #[arg(long, default_value_t = 900, requires = "daemon", value_parser = clap::value_parser!(u64).range(1..))]
+    pub shallow_interval: u64,
+
+    /// Amount of pages refreshed every --shallow-interval
+    #[arg(long, default_value_t = 5, requires = "daemon")]
+    pub shallow_pages: u32,
+
+    /// Seconds between crawls of the whole listing
+    #[arg(long, default_value_t = 21600, requires = "daemon", value_parser = clap::value_parser!(u64).range(1..))]
+    pub deep_interval: u64,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct PageOptions {
     /// Initial page to collect NFT
     #[arg(short, long, default_value_t = 1)]
-    pub initial_page: u8,
+    pub initial_page: u32,
 
     /// Final page to collect NFT
     #[arg(short, long, default_value_t = 5)]
-    pub final_page: u8,
+    pub final_page: u32,
 
-    /// If the backend should drop the database or not. [Default: false]
+    /// Keep collecting pages until the listing is exhausted, ignoring --final-page [default: false]
     #[arg(short, long, default_value_t = false)]
-    pub db_drop: bool,
+    pub all_pages: bool,
 
-    /// Local Development [default: false]
-    #[arg(short, long, default_value_t = false)]
-    pub local: bool,
+    /// Maximum amount of pages collected by --all-pages
+    #[arg(long, requires = "all_pages")]
+    pub max_pages: Option<u32>,
+
+    /// Stop --all-pages once a whole page is made of already known listings [default: false]
+    #[arg(long, default_value_t = false, requires = "all_pages")]
+    pub stop_on_known: bool,
+}
+
+/// Filters of the /nft/lists endpoint, 0 means no filter
+#[derive(Args, Debug, Clone)]
+#[command(next_help_heading = "Listing filters")]
+pub struct ListingFilter {
+    /// Character class
+    #[arg(long, default_value_t = 0)]
+    pub class: u8,
+
+    /// Minimum character level
+    #[arg(long, default_value_t = 0)]
+    pub lev_min: u32,
+
+    /// Maximum character level
+    #[arg(long, default_value_t = 0)]
+    pub lev_max: u32,
+
+    /// Minimum power score
+    #[arg(long, default_value_t = 0)]
+    pub power_min: u32,
+
+    /// Maximum power score
+    #[arg(long, default_value_t = 0)]
+    pub power_max: u32,
+
+    /// Minimum price
+    #[arg(long, default_value_t = 0)]
+    pub price_min: u32,
+
+    /// Maximum price
+    #[arg(long, default_value_t = 0)]
+    pub price_max: u32,
+
+    /// Listing order
+    #[arg(long, default_value = "latest")]
+    pub sort: String,
+}
+
+impl ListingFilter {
+    pub fn list_path(&self, page: u32) -> String {
+        format!(
+            "/nft/lists?listType=sale&class={class}&levMin={lev_min}&levMax={lev_max}&powerMin={power_min}&powerMax={power_max}&priceMin={price_min}&priceMax={price_max}&sort={sort}&page={page}&languageCode=en",
+            class = self.class,
+            lev_min = self.lev_min,
+            lev_max = self.lev_max,
+            power_min = self.power_min,
+            power_max = self.power_max,
+            price_min = self.price_min,
+            price_max = self.price_max,
+            sort = self.sort,
+            page = page
+        )
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.class == 0
+            && self.lev_min == 0
+            && self.lev_max == 0
+            && self.power_min == 0
+            && self.power_max == 0
+            && self.price_min == 0
+            && self.price_max == 0
+    }
+
+    /// One filter per class and power band, together they cover the same market as `self`
+    pub fn partitions(&self, classes: &[u8], power_bands: &[u32]) -> Vec<ListingFilter> {
+        let mut bands = power_bands.to_vec();
+        bands.sort_unstable();
+        bands.dedup();
+
+        let mut partitions = Vec::new();
+        for class in classes {
+            for (index, power_min) in bands.iter().enumerate() {
+                let power_max = bands.get(index + 1).map_or(0, |next| next - 1);
+                partitions.push(ListingFilter {
+                    class: *class,
+                    power_min: *power_min,
+                    power_max,
+                    ..self.clone()
+                });
+            }
+        }
+
+        partitions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Parser)]
+    struct TestCrawl {
+        #[command(flatten)]
+        options: CrawlOptions,
+    }
+
+    fn options(args: &[&str]) -> CrawlOptions {
+        TestCrawl::parse_from(std::iter::once("crawl").chain(args.iter().copied())).options
+    }
+
+    #[test]
+    fn partitions_split_power_bands_per_class() {
+        let options = options(&["--partition", "--lev-min", "60"]);
+        let bands: Vec<(u8, u32, u32)> = options
+            .filter
+            .partitions(&[1, 5], &[200000, 0, 100000, 100000])
+            .iter()
+            .map(|filter| (filter.class, filter.power_min, filter.power_max))
+            .collect();
+
+        assert_eq!(
+            bands,
+            [
+                (1, 0, 99999),
+                (1, 100000, 199999),
+                (1, 200000, 0),
+                (5, 0, 99999),
+                (5, 100000, 199999),
+                (5, 200000, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn partitions_keep_the_other_filters() {
+        let options = options(&["--partition", "--lev-min", "60", "--price-max", "5000"]);
+        let partitions = options.filter.partitions(&[3], &[0]);
+
+        assert_eq!(partitions.len(), 1);
+        assert_eq!((partitions[0].lev_min, partitions[0].price_max), (60, 5000));
+        assert_eq!((partitions[0].power_min, partitions[0].power_max), (0, 0));
+    }
+
+    #[test]
+    fn covers_whole_market_only_with_every_class_from_power_0() {
+        assert!(options(&[]).covers_whole_market());
+        assert!(options(&["--partition"]).covers_whole_market());
+        assert!(options(&["--partition", "--power-bands", "0,100000"]).covers_whole_market());
+
+        assert!(!options(&["--class", "5"]).covers_whole_market());
+        assert!(!options(&["--all-pages", "--initial-page", "2"]).covers_whole_market());
+        assert!(!options(&["--partition", "-i", "3"]).covers_whole_market());
+        assert!(!options(&["--partition", "--partition-classes", "5"]).covers_whole_market());
+        assert!(!options(&["--partition", "--power-bands", "100000,200000"]).covers_whole_market());
+        assert!(!options(&["--partition", "--lev-min", "60"]).covers_whole_market());
+    }
 }
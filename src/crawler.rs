@@ -0,0 +1,989 @@
+use std::time::{Duration, Instant};
+use tokio::task::{JoinError, JoinSet};
+use tokio::time::MissedTickBehavior;
+
+use crate::cli::{CrawlOptions, DaemonOptions, FetchArgs, ListingFilter, PageOptions};
+use crate::db;
+use crate::error::CrawlError;
+use crate::responses::inventory::InventoryResponse;
+use crate::responses::magic_orb::{get_nft_magic_orb, MagicOrbResponse};
+use crate::responses::magic_stone::{get_nft_magic_stone, MagicStoneResponseObject};
+use crate::responses::mystical_piece::{get_nft_mystical_piece, MysticalPieceResponseObject};
+use crate::responses::nft::{Nft, NftListResponse};
+use crate::responses::spirits::{get_nft_spirits, SpiritsObject};
+use crate::responses::succession::{get_nft_succession, SuccessionResponse};
+use crate::responses::ticket::get_nft_tickets;
+use crate::responses::{
+    assets::get_nft_assets, building::get_nft_buildings, codex::get_nft_codex,
+    holy_stuff::get_nft_holy_stuff, inventory::get_nft_inventory, potentials::get_nft_potentials,
+    skills::get_nft_skills, stats::get_nft_stats, summary::get_nft_summary,
+    training::get_nft_training,
+};
+use crate::responses::localization::{get_english_names, get_localized_names};
+use crate::utils::{get_response, nft_description_error, AppState};
+use reqwest_middleware::ClientWithMiddleware;
+use serde::Serialize;
+use sqlx::{Pool, Postgres};
+
+// how long a listing page waits when the API answers that it is rate limiting, and how often
+const RATE_LIMITED_PAUSE: Duration = Duration::from_secs(30);
+const RATE_LIMITED_RETRIES: u32 = 3;
+
+// failed pages in a row after which --all-pages gives up on the listing
+const MAX_FAILED_PAGES: u32 = 3;
+
+#[derive(Debug, Default)]
+pub struct PageSummary {
+    pub seqs: Vec<i32>,
+    pub new: u32,
+    pub updated: u32,
+    pub unchanged: u32,
+    /// Listed on the page but gone by the time it was crawled
+    pub gone: u32,
+    pub failed: u32,
+}
+
+impl PageSummary {
+    pub fn is_empty(&self) -> bool {
+        self.seqs.is_empty()
+    }
+
+    pub fn all_known(&self) -> bool {
+        !self.is_empty() && (self.updated + self.unchanged + self.gone) as usize == self.seqs.len()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CrawlSummary {
+    pub listed_seqs: Vec<i32>,
+    pub pages_fetched: u32,
+    pub failed_pages: u32,
+    pub listing_exhausted: bool,
+    pub new_characters: u32,
+    pub updated_characters: u32,
+    /// Known characters whose price did not change
+    pub skipped_characters: u32,
+    pub failed_characters: u32,
+}
+
+impl CrawlSummary {
+    fn merge(&mut self, other: CrawlSummary) {
+        self.listed_seqs.extend(other.listed_seqs);
+        self.pages_fetched += other.pages_fetched;
+        self.failed_pages += other.failed_pages;
+        self.listing_exhausted &= other.listing_exhausted;
+        self.new_characters += other.new_characters;
+        self.updated_characters += other.updated_characters;
+        self.skipped_characters += other.skipped_characters;
+        self.failed_characters += other.failed_characters;
+    }
+
+    fn add_page(&mut self, page: anyhow::Result<PageSummary>) {
+        match page {
+            Ok(page) => {
+                self.pages_fetched += 1;
+                self.listing_exhausted |= page.is_empty();
+                self.listed_seqs.extend(page.seqs);
+                self.new_characters += page.new;
+                self.updated_characters += page.updated;
+                self.skipped_characters += page.unchanged;
+                self.failed_characters += page.failed;
+            }
+            Err(err) => {
+                self.failed_pages += 1;
+                tracing::error!("Error spawning NFT list: {}", err)
+            }
+        }
+    }
+
+    // only a crawl that saw the whole listing can tell which characters are gone
+    pub fn covers_whole_listing(&self) -> bool {
+        self.listing_exhausted && self.failed_pages == 0 && !self.listed_seqs.is_empty()
+    }
+}
+
+pub enum NftOutcome {
+    New,
+    /// Already stored, its price changed
+    Updated,
+    /// Already stored with the same price
+    Unchanged,
+}
+
+/// Crawls the market once, unless another instance is already crawling
+pub async fn run_crawl(state: AppState, options: &CrawlOptions) -> anyhow::Result<()> {
+    let Some(pool) = state.db.clone() else {
+        // dry runs have no lock to take nor report to store
+        let crawl_summary = crawl_market(state.clone(), options).await?;
+        log_summary(&state, &crawl_summary);
+        return Ok(());
+    };
+
+    let mut lock_connection = pool.acquire().await?;
+    if !db::try_lock_crawl(&mut lock_connection).await? {
+        tracing::warn!("Another crawl is already running, skipping this one");
+        return Ok(());
+    }
+
+    // the lock is held by this session until unlocked, every exit from here on has to unlock it
+    let result = crawl_and_report(&state, &pool, options).await;
+    db::unlock_crawl(&mut lock_connection).await?;
+
+    result
+}
+
+/// Crawls the market and stores the report of the run
+async fn crawl_and_report(
+    state: &AppState,
+    pool: &Pool<Postgres>,
+    options: &CrawlOptions,
+) -> anyhow::Result<()> {
+    let now = Instant::now();
+    state.item_details.clear();
+    let final_page = (!options.pages.all_pages).then_some(options.pages.final_page);
+    let run_id = db::start_crawl_run(pool, options.pages.initial_page, final_page).await?;
+    state.requests.take();
+
+    let crawl_summary = crawl_market(state.clone(), options).await?;
+    let delisted = mark_delisted(pool, options, &crawl_summary).await;
+    log_summary(state, &crawl_summary);
+    let finished = db::finish_crawl_run(
+        pool,
+        run_id,
+        &crawl_summary,
+        &state.requests.take(),
+        now.elapsed(),
+    )
+    .await;
+
+    let elapsed = now.elapsed();
+    tracing::info!("retrieve_and_save_nft function time: {:#?}", elapsed);
+
+    delisted.and(finished.map_err(Into::into))
+}
+
+async fn crawl_market(state: AppState, options: &CrawlOptions) -> Result<CrawlSummary, JoinError> {
+    if options.partition {
+        crawl_partitions(
+            state,
+            options
+                .filter
+                .partitions(&options.partition_classes, &options.power_bands),
+            &options.pages,
+        )
+        .await
+    } else {
+        crawl_listing(state, options.filter.clone(), &options.pages).await
+    }
+}
+
+fn log_summary(state: &AppState, crawl_summary: &CrawlSummary) {
+    tracing::info!(
+        "{} pages fetched, {} failed, {} new characters, {} updated, {} skipped, {} failed",
+        crawl_summary.pages_fetched,
+        crawl_summary.failed_pages,
+        crawl_summary.new_characters,
+        crawl_summary.updated_characters,
+        crawl_summary.skipped_characters,
+        crawl_summary.failed_characters
+    );
+
+    let unknown_items = state.tradable.unknown_items();
+    if !unknown_items.is_empty() {
+        tracing::warn!(
+            "{} item ids are not in the tradable item list, rebuild it with the catalog command: {}",
+            unknown_items.len(),
+            unknown_items.join(", ")
+        );
+    }
+}
+
+async fn mark_delisted(
+    pool: &Pool<Postgres>,
+    options: &CrawlOptions,
+    crawl_summary: &CrawlSummary,
+) -> anyhow::Result<()> {
+    if !options.covers_whole_market() {
+        tracing::info!("Only part of the market was crawled, skipping delisted detection");
+    } else if crawl_summary.covers_whole_listing() {
+        let delisted = db::mark_delisted(pool, &crawl_summary.listed_seqs).await?;
+        tracing::info!("{} characters marked as delisted", delisted);
+    } else {
+        tracing::info!("Listing was not crawled to the end, skipping delisted detection");
+    }
+
+    Ok(())
+}
+
+/// Refreshes the first pages every `shallow_interval` and the whole listing every `deep_interval`
+pub async fn run_daemon(
+    state: AppState,
+    options: &CrawlOptions,
+    daemon: &DaemonOptions,
+) -> anyhow::Result<()> {
+    let shallow_options = CrawlOptions {
+        pages: PageOptions {
+            initial_page: 1,
+            final_page: daemon.shallow_pages,
+            all_pages: false,
+            max_pages: None,
+            stop_on_known: false,
+        },
+        partition: false,
+        ..options.clone()
+    };
+    let deep_options = CrawlOptions {
+        pages: PageOptions {
+            initial_page: 1,
+            all_pages: true,
+            stop_on_known: false,
+            ..options.pages.clone()
+        },
+        ..options.clone()
+    };
+
+    let mut shallow = tokio::time::interval(Duration::from_secs(daemon.shallow_interval));
+    let mut deep = tokio::time::interval(Duration::from_secs(daemon.deep_interval));
+    shallow.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    deep.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            biased;
+            _ = deep.tick() => {
+                tracing::info!("Starting scheduled crawl of the whole listing");
+                if let Err(error) = run_crawl(state.clone(), &deep_options).await {
+                    tracing::error!("Scheduled crawl failed: {:#?}", error);
+                }
+                // the first pages were just crawled
+                shallow.reset();
+            }
+            _ = shallow.tick() => {
+                tracing::info!("Starting scheduled crawl of the first {} pages", daemon.shallow_pages);
+                if let Err(error) = run_crawl(state.clone(), &shallow_options).await {
+                    tracing::error!("Scheduled crawl failed: {:#?}", error);
+                }
+            }
+        }
+    }
+}
+
+pub async fn crawl_listing(
+    state: AppState,
+    filter: ListingFilter,
+    pages: &PageOptions,
+) -> Result<CrawlSummary, JoinError> {
+    if pages.all_pages {
+        return Ok(crawl_until_exhausted(
+            state,
+            filter,
+            pages.initial_page,
+            pages.max_pages,
+            pages.stop_on_known,
+        )
+        .await);
+    }
+
+    crawl_page_range(state, filter, pages.initial_page, pages.final_page).await
+}
+
+pub async fn crawl_partitions(
+    state: AppState,
+    partitions: Vec<ListingFilter>,
+    pages: &PageOptions,
+) -> Result<CrawlSummary, JoinError> {
+    let mut summary = CrawlSummary {
+        listing_exhausted: true,
+        ..Default::default()
+    };
+
+    for filter in partitions {
+        match filter.power_max {
+            0 => tracing::info!(
+                "Crawling class {} with power from {}",
+                filter.class,
+                filter.power_min
+            ),
+            power_max => tracing::info!(
+                "Crawling class {} with power between {} and {}",
+                filter.class,
+                filter.power_min,
+                power_max
+            ),
+        }
+        summary.merge(crawl_listing(state.clone(), filter, pages).await?);
+    }
+
+    Ok(summary)
+}
+
+pub async fn crawl_page_range(
+    state: AppState,
+    filter: ListingFilter,
+    initial_page: u32,
+    final_page: u32,
+) -> Result<CrawlSummary, JoinError> {
+    let mut join_set = JoinSet::new();
+
+    for i in initial_page..final_page + 1 {
+        join_set.spawn(retrieve_page(state.clone(), filter.clone(), i));
+    }
+
+    let mut summary = CrawlSummary::default();
+    while let Some(res) = join_set.join_next().await {
+        summary.add_page(res?);
+    }
+
+    Ok(summary)
+}
+
+pub async fn crawl_until_exhausted(
+    state: AppState,
+    filter: ListingFilter,
+    initial_page: u32,
+    max_pages: Option<u32>,
+    stop_on_known: bool,
+) -> CrawlSummary {
+    let mut summary = CrawlSummary::default();
+    let mut failed_in_a_row = 0;
+
+    for page_index in initial_page.. {
+        if max_pages.is_some_and(|max_pages| page_index - initial_page >= max_pages) {
+            tracing::info!("Reached the limit of {} pages", page_index - initial_page);
+            break;
+        }
+
+        let page = retrieve_page(state.clone(), filter.clone(), page_index).await;
+
+        let (is_empty, all_known) = match &page {
+            Ok(page) => {
+                failed_in_a_row = 0;
+                (page.is_empty(), page.all_known())
+            }
+            Err(_) => {
+                failed_in_a_row += 1;
+                (false, false)
+            }
+        };
+        let maintenance = page
+            .as_ref()
+            .is_err_and(|error| matches!(error.downcast_ref(), Some(CrawlError::Maintenance { .. })));
+        summary.add_page(page);
+
+        if maintenance {
+            tracing::warn!("Mir4 API is under maintenance, stopping at page {}", page_index);
+            break;
+        }
+        // without --max-pages nothing else ends the loop when the API keeps failing, e.g. past
+        // the deepest page it serves
+        if failed_in_a_row >= MAX_FAILED_PAGES {
+            tracing::warn!("{} pages in a row failed, stopping at page {}", failed_in_a_row, page_index);
+            break;
+        }
+
+        if is_empty {
+            tracing::info!("Page {} is empty, the listing is exhausted", page_index);
+            break;
+        }
+        if stop_on_known && all_known {
+            tracing::info!("Every listing on page {} is already known, stopping", page_index);
+            break;
+        }
+    }
+
+    summary
+}
+
+/// Crawls a listing page, asking for it again after a pause while the API is rate limiting
+async fn retrieve_page(
+    state: AppState,
+    filter: ListingFilter,
+    page_index: u32,
+) -> anyhow::Result<PageSummary> {
+    for _ in 0..RATE_LIMITED_RETRIES {
+        match retrieve_and_save_nft(state.clone(), filter.clone(), page_index).await {
+            Err(error) if matches!(error.downcast_ref(), Some(CrawlError::RateLimited { .. })) => {
+                tracing::warn!(
+                    "Mir4 API is rate limiting, asking for page {} again in {:?}",
+                    page_index,
+                    RATE_LIMITED_PAUSE
+                );
+                tokio::time::sleep(RATE_LIMITED_PAUSE).await;
+            }
+            page => return page,
+        }
+    }
+
+    retrieve_and_save_nft(state, filter, page_index).await
+}
+
+pub async fn retrieve_and_save_nft(
+    state: AppState,
+    filter: ListingFilter,
+    page_index: u32,
+) -> anyhow::Result<PageSummary> {
+    let request_path = filter.list_path(page_index);
+
+    let response_json: NftListResponse = get_response(&state.client, request_path).await?;
+
+    let page_seqs: Vec<i32> = response_json.data.lists.iter().map(|nft| nft.seq).collect();
+
+    let nft_list = serde_json::to_value(response_json.data.lists)?;
+
+    let list = match nft_list {
+        serde_json::Value::Array(arr) => arr,
+        _ => Vec::new(),
+    };
+
+    let tasks: Vec<_> = list
+        .into_iter()
+        .map(|character| {
+            (
+                character.clone(),
+                tokio::spawn(dump_nft(character, state.clone(), false)),
+            )
+        })
+        .collect();
+
+    let mut page = PageSummary {
+        seqs: page_seqs,
+        ..Default::default()
+    };
+    for (character, task) in tasks {
+        match task.await? {
+            Ok(NftOutcome::New) => page.new += 1,
+            Ok(NftOutcome::Updated) => page.updated += 1,
+            Ok(NftOutcome::Unchanged) => page.unchanged += 1,
+            Err(CrawlError::NotFound { .. }) => {
+                page.gone += 1;
+                tracing::info!(
+                    "{}",
+                    nft_description_error("Left the market while crawled, skipping", character)
+                );
+            }
+            Err(error) => {
+                page.failed += 1;
+                tracing::error!(
+                    "{} {}",
+                    nft_description_error("Error dumping nft", character.clone()),
+                    error
+                );
+                record_failure(&state, &character, "character", &error).await;
+            }
+        }
+    }
+
+    Ok(page)
+}
+
+/// Crawls and stores a character from its listing entry. With `force` a character that is
+/// already stored is crawled again and replaced.
+pub async fn dump_nft(
+    nft_data: serde_json::Value,
+    state: AppState,
+    force: bool,
+) -> Result<NftOutcome, CrawlError> {
+    let AppState {
+        db,
+        ndjson,
+        client,
+        languages,
+        ..
+    } = state.clone();
+
+    let character: Nft = serde_json::from_value(nft_data.clone())
+        .map_err(|error| CrawlError::decode(error, &nft_data))?;
+
+    let Some(pool) = db else {
+        // dry run, every character is crawled and written out
+        let mut fetched = fetch_nft(character, &state).await;
+        if let Some(error) = fetched.unavailable() {
+            return Err(error);
+        }
+        if let Some(ndjson) = ndjson {
+            ndjson.write(&fetched)?;
+        }
+        return Ok(NftOutcome::New);
+    };
+
+    let db_transport_id: (bool,) = sqlx::query_as(
+        "
+      select
+          exists (
+            select
+              1
+            from
+              nft
+            where
+              transport_id = $1
+      );",
+    )
+    .bind(character.transport_id)
+    .fetch_one(&pool)
+    .await?;
+
+    if !force {
+        db::add_price_history(&pool, &character)
+            .await
+            .unwrap_or_else(|_| { tracing::error!("{}", nft_description_error(
+                "Fail to add nft price history",
+                nft_data.clone(),
+            )) });
+    }
+
+    if db_transport_id.0 && !force {
+        tracing::info!(
+            "transport_id: {} exist in the database, updating its price",
+            character.transport_id
+        );
+        let price_changed = db::update_nft_price(&pool, &character).await?;
+        return Ok(if price_changed {
+            NftOutcome::Updated
+        } else {
+            NftOutcome::Unchanged
+        });
+    }
+
+    tracing::info!(
+        "Dumping character with the name of {:#?}...",
+        character.character_name
+    );
+
+    let mut fetched = fetch_nft(character, &state).await;
+    if let Some(error) = fetched.unavailable() {
+        return Err(error);
+    }
+    store_nft(&pool, &nft_data, &mut fetched).await?;
+    let character = fetched.character;
+
+    if !languages.is_empty() {
+        store_localized_names(&pool, &client, &character, &languages).await;
+    }
+
+    Ok(NftOutcome::New)
+}
+
+/// Stores the names of a character's stats, skills, buildings, holy stuff and items in every
+/// language, the english names stored next to them are fetched once for all of them
+async fn store_localized_names(
+    pool: &Pool<Postgres>,
+    client: &ClientWithMiddleware,
+    character: &Nft,
+    languages: &[String],
+) {
+    let english_names =
+        match get_english_names(character.transport_id, character.class, client).await {
+            Ok(names) => names,
+            Err(error) => {
+                tracing::error!("Fail to get english names: {:#?}", error);
+                return;
+            }
+        };
+
+    for language_code in languages {
+        let localized_names = get_localized_names(
+            character.transport_id,
+            character.class,
+            client,
+            &english_names,
+            language_code,
+        )
+        .await;
+
+        match localized_names {
+            Ok(names) => db::add_localized_names(pool, language_code, &names)
+                .await
+                .unwrap_or_else(|error| {
+                    tracing::error!("Fail to add {} localized names: {:#?}", language_code, error)
+                }),
+            Err(error) => {
+                tracing::error!("Fail to get {} localized names: {:#?}", language_code, error)
+            }
+        }
+    }
+}
+
+/// A character with every section fetched from the API, sections that could not be fetched are
+/// `None` and listed in `failures`
+#[derive(Serialize)]
+pub struct FetchedNft {
+    #[serde(flatten)]
+    pub character: Nft,
+    pub inventory: Option<InventoryResponse>,
+    pub succession: Option<SuccessionResponse>,
+    pub spirits: Option<SpiritsObject>,
+    pub magic_orb: Option<MagicOrbResponse>,
+    pub magic_stone: Option<MagicStoneResponseObject>,
+    pub mystical_piece: Option<MysticalPieceResponseObject>,
+    #[serde(skip)]
+    pub failures: Vec<(&'static str, CrawlError)>,
+}
+
+impl FetchedNft {
+    /// Takes the error of a character the API had nothing for, because it left the market or
+    /// the API went into maintenance, there is nothing worth storing then
+    pub fn unavailable(&mut self) -> Option<CrawlError> {
+        let index = self.failures.iter().position(is_unavailable)?;
+
+        Some(self.failures.swap_remove(index).1)
+    }
+}
+
+// the inventory is asked for first, on its own, the other sections are not asked for without it
+fn is_unavailable((name, error): &(&'static str, CrawlError)) -> bool {
+    *name == "inventory" && matches!(error, CrawlError::NotFound { .. } | CrawlError::Maintenance { .. })
+}
+
+/// Fetches every section of a character from its listing entry
+pub async fn fetch_nft(mut character: Nft, state: &AppState) -> FetchedNft {
+    let AppState {
+        client,
+        tradable,
+        item_details,
+        ..
+    } = state;
+    let nft_inventory =
+        get_nft_inventory(character.transport_id, client.clone(), tradable).await;
+    let mut failures = Vec::new();
+    let nft_inventory = section(&mut failures, "inventory", nft_inventory);
+    if failures.iter().any(is_unavailable) {
+        return FetchedNft {
+            character,
+            inventory: None,
+            succession: None,
+            spirits: None,
+            magic_orb: None,
+            magic_stone: None,
+            mystical_piece: None,
+            failures,
+        };
+    }
+    // sections that look items up in the inventory still work without it, only without item details
+    let inventory_items = nft_inventory
+        .as_ref()
+        .map(|inventory| inventory.inventory.clone())
+        .unwrap_or_default();
+
+    let (
+        succession,
+        spirits,
+        magic_orb,
+        magic_stone,
+        mystical_piece,
+        tickets,
+        summary,
+        stats,
+        skills,
+        training,
+        buildings,
+        assets,
+        potentials,
+        holy_stuff,
+        codex,
+    ) = tokio::join!(
+        get_nft_succession(
+            character.transport_id,
+            client.clone(),
+            character.class,
+            inventory_items.clone(),
+            item_details
+        ),
+        get_nft_spirits(character.transport_id, client.clone()),
+        get_nft_magic_orb(character.transport_id, client.clone()),
+        get_nft_magic_stone(
+            character.transport_id,
+            character.class,
+            client.clone(),
+            inventory_items.clone(),
+            tradable,
+            item_details
+        ),
+        get_nft_mystical_piece(
+            character.transport_id,
+            character.class,
+            client.clone(),
+            inventory_items.clone(),
+            tradable,
+            item_details
+        ),
+        get_nft_tickets(inventory_items.clone()),
+        get_nft_summary(
+            character.seq,
+            character.transport_id,
+            character.class,
+            client.clone(),
+            inventory_items.clone(),
+            tradable,
+            item_details
+        ),
+        get_nft_stats(character.transport_id, client.clone()),
+        get_nft_skills(character.transport_id, character.class, client.clone()),
+        get_nft_training(character.transport_id, client.clone()),
+        get_nft_buildings(character.transport_id, client.clone()),
+        get_nft_assets(character.transport_id, client.clone()),
+        get_nft_potentials(character.transport_id, client.clone()),
+        get_nft_holy_stuff(character.transport_id, client.clone()),
+        get_nft_codex(character.transport_id, client.clone())
+    );
+
+    let succession = section(&mut failures, "succession", succession);
+    let spirits = section(&mut failures, "spirits", spirits);
+    let magic_orb = section(&mut failures, "magic_orb", magic_orb);
+    let magic_stone = section(&mut failures, "magic_stone", magic_stone);
+    let mystical_piece = section(&mut failures, "mystical_piece", mystical_piece);
+
+    if let Some(tickets) = section(&mut failures, "tickets", tickets) {
+        character.tickets = tickets;
+    }
+    if let Some(summary) = section(&mut failures, "summary", summary) {
+        character.trade_type = summary.trade_type;
+        character.world_name = summary.world_name;
+        character.equip_items = summary.equip_items;
+    }
+    if let Some(stats) = section(&mut failures, "stats", stats) {
+        character.stats = stats;
+    }
+    if let Some(skills) = section(&mut failures, "skills", skills) {
+        character.skills = skills;
+    }
+    if let Some(training) = section(&mut failures, "training", training) {
+        character.training = training;
+    }
+    if let Some(buildings) = section(&mut failures, "buildings", buildings) {
+        character.buildings = buildings;
+    }
+    if let Some(assets) = section(&mut failures, "assets", assets) {
+        character.assets = assets;
+    }
+    if let Some(potentials) = section(&mut failures, "potentials", potentials) {
+        character.potentials = potentials;
+    }
+    if let Some(holy_stuff) = section(&mut failures, "holy_stuff", holy_stuff) {
+        character.holy_stuff = holy_stuff;
+    }
+    if let Some(codex) = section(&mut failures, "codex", codex) {
+        character.codex = codex;
+    }
+
+    for (name, error) in &failures {
+        tracing::warn!(
+            "transport_id: {} is missing its {} section: {}",
+            character.transport_id,
+            name,
+            error
+        );
+    }
+    character.missing_sections = failures.iter().map(|(name, _)| name.to_string()).collect();
+
+    FetchedNft {
+        character,
+        inventory: nft_inventory,
+        succession,
+        spirits,
+        magic_orb,
+        magic_stone,
+        mystical_piece,
+        failures,
+    }
+}
+
+/// Stores a fetched character, replacing any stored copy, and records its missing sections
+pub async fn store_nft(
+    pool: &Pool<Postgres>,
+    listing: &serde_json::Value,
+    fetched: &mut FetchedNft,
+) -> Result<(), CrawlError> {
+    let sections = db::NftSections {
+        inventory: fetched.inventory.as_ref(),
+        succession: fetched.succession.as_ref(),
+        spirits: fetched.spirits.as_ref(),
+        magic_orb: fetched.magic_orb.as_ref(),
+        magic_stone: fetched.magic_stone.as_ref(),
+        mystical_piece: fetched.mystical_piece.as_ref(),
+    };
+
+    db::add_nft_with_sections(pool, &mut fetched.character, &sections).await?;
+
+    // the character is stored, its missing sections are kept for retry-failures
+    for (name, error) in &fetched.failures {
+        db::add_crawl_failure(pool, listing, name, error)
+            .await
+            .unwrap_or_else(|error| tracing::error!("Fail to record crawl failure: {:#?}", error));
+    }
+
+    Ok(())
+}
+
+/// Keeps a fetched section, or records it as missing so the character is still stored without it
+fn section<T>(
+    failures: &mut Vec<(&'static str, CrawlError)>,
+    name: &'static str,
+    result: Result<T, CrawlError>,
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            failures.push((name, error));
+            None
+        }
+    }
+}
+
+async fn record_failure(
+    state: &AppState,
+    character: &serde_json::Value,
+    section: &str,
+    error: &CrawlError,
+) {
+    let Some(pool) = &state.db else {
+        return;
+    };
+
+    db::add_crawl_failure(pool, character, section, error)
+        .await
+        .unwrap_or_else(|error| tracing::error!("Fail to record crawl failure: {:#?}", error));
+}
+
+/// Crawls again every character with a recorded failure
+pub async fn retry_failures(state: AppState) -> anyhow::Result<()> {
+    let characters = db::get_crawl_failures(state.pool()?).await?;
+    tracing::info!("Retrying {} characters", characters.len());
+
+    let mut still_failing = 0;
+    for character in characters {
+        let result = dump_nft(character.clone(), state.clone(), true).await;
+        if let (Err(CrawlError::NotFound { .. }), Some(transport_id)) =
+            (&result, character["transport_id"].as_i64())
+        {
+            tracing::info!(
+                "{}",
+                nft_description_error("Left the market, forgetting its failures", character.clone())
+            );
+            db::clear_crawl_failures(state.pool()?, transport_id as i32).await?;
+            continue;
+        }
+        if let Err(error) = result {
+            tracing::error!(
+                "{} {}",
+                nft_description_error("Error dumping nft", character.clone()),
+                error
+            );
+            record_failure(&state, &character, "character", &error).await;
+            still_failing += 1;
+        }
+    }
+
+    let remaining = db::get_crawl_failures(state.pool()?).await?.len();
+    tracing::info!(
+        "{} characters could not be stored, {} still have missing sections or errors",
+        still_failing,
+        remaining
+    );
+
+    Ok(())
+}
+
+/// Crawls a single character found by transport ID or seq and prints it as JSON, with `save` it
+/// replaces the stored copy
+pub async fn fetch_character(state: AppState, args: &FetchArgs) -> anyhow::Result<()> {
+    let (column, value) = match (args.transport_id, args.seq) {
+        (Some(transport_id), _) => ("transport_id", transport_id),
+        (None, Some(seq)) => ("seq", seq),
+        (None, None) => anyhow::bail!("either --transport-id or --seq is required"),
+    };
+
+    let listing = match find_listing(&state, args).await? {
+        Some(listing) => listing,
+        None => {
+            let Some(pool) = &state.db else {
+                anyhow::bail!(
+                    "no character with {} {} is in the first {} listing pages",
+                    column,
+                    value,
+                    args.max_pages
+                );
+            };
+            tracing::info!("{} {} is not in the listing, looking it up in the database", column, value);
+            db::get_stored_listing(pool, column, value.into())
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("no character with {} {} was found", column, value))?
+        }
+    };
+
+    let character: Nft = serde_json::from_value(listing.clone())
+        .map_err(|error| CrawlError::decode(error, &listing))?;
+    let mut fetched = fetch_nft(character, &state).await;
+    if let Some(error) = fetched.unavailable() {
+        return Err(error.into());
+    }
+
+    if args.save {
+        store_nft(state.pool()?, &listing, &mut fetched).await?;
+        tracing::info!("Stored character {}", fetched.character.transport_id);
+    }
+
+    println!("{}", serde_json::to_string_pretty(&fetched)?);
+
+    Ok(())
+}
+
+/// Searches the first `max_pages` listing pages for a character
+async fn find_listing(state: &AppState, args: &FetchArgs) -> anyhow::Result<Option<serde_json::Value>> {
+    for page in 1..=args.max_pages {
+        let response_json: NftListResponse =
+            get_response(&state.client, args.filter.list_path(page)).await?;
+        if response_json.data.lists.is_empty() {
+            break;
+        }
+
+        let found = response_json
+            .data
+            .lists
+            .into_iter()
+            .find(|nft| Some(nft.transport_id) == args.transport_id || Some(nft.seq) == args.seq);
+        if let Some(nft) = found {
+            return Ok(Some(serde_json::to_value(nft)?));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Prints the most recent crawl reports
+pub async fn print_runs(pool: &Pool<Postgres>, limit: u32) -> anyhow::Result<()> {
+    println!(
+        "{:>6}  {:<19}  {:>7}  {:>9}  {:>5}  {:>7}  {:>7}  {:>6}  {:>8}  {:>10}",
+        "id", "started", "pages", "ok/failed", "new", "updated", "skipped", "failed", "requests", "duration"
+    );
+
+    for run in db::get_crawl_runs(pool, limit).await? {
+        let pages = match run["final_page"].as_i64() {
+            Some(final_page) => format!("{}-{}", run["initial_page"], final_page),
+            None => format!("{}-", run["initial_page"]),
+        };
+        let requests: u64 = run["requests"]
+            .as_object()
+            .map(|requests| requests.values().filter_map(|count| count.as_u64()).sum())
+            .unwrap_or_default();
+        let duration = match run["duration_ms"].as_i64() {
+            Some(duration_ms) => format!("{:.1}s", duration_ms as f64 / 1000.0),
+            None => "unfinished".to_string(),
+        };
+
+        println!(
+            "{:>6}  {:<19}  {:>7}  {:>9}  {:>5}  {:>7}  {:>7}  {:>6}  {:>8}  {:>10}",
+            run["id"].to_string(),
+            run["started_at"].as_str().unwrap_or_default(),
+            pages,
+            format!("{}/{}", run["pages_fetched"], run["failed_pages"]),
+            run["new_characters"].to_string(),
+            run["updated_characters"].to_string(),
+            run["skipped_characters"].to_string(),
+            run["failed_characters"].to_string(),
+            requests,
+            duration
+        );
+    }
+
+    Ok(())
+}
@@ -1,5 +1,12 @@
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use sqlx::{postgres::PgPoolOptions, PgConnection, PgExecutor, Pool, Postgres};
+use std::collections::HashMap;
 use std::env;
+use std::time::Duration;
+use crate::cli::ExportFilter;
+use crate::crawler::CrawlSummary;
+use dump_trade_items::CatalogItem;
+use crate::error::CrawlError;
+use crate::responses::localization::LocalizedName;
 use crate::responses::magic_orb::MagicOrbResponse;
 use crate::responses::magic_stone::MagicStoneResponseObject;
 use crate::responses::mystical_piece::MysticalPieceResponseObject;
@@ -7,17 +14,46 @@ use crate::responses::spirits::SpiritsObject;
 use crate::responses::{inventory::InventoryResponse, nft::Nft, succession::SuccessionResponse};
 
 pub async fn create_pool() -> Result<Pool<Postgres>, sqlx::Error> {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let database_url = env::var("DATABASE_URL").map_err(|_| {
+        sqlx::Error::Configuration("DATABASE_URL must be set, crawl --ndjson and fetch without --save run without a database".into())
+    })?;
     PgPoolOptions::new()
         .max_connections(5)
         .connect(&database_url)
         .await
 }
 
-pub async fn add_nft(pool: &Pool<Postgres>, character: &Nft) -> Result<(), sqlx::Error> {
+/// Applies the migrations embedded from `migrations/` that were not applied yet
+pub async fn migrate(pool: &Pool<Postgres>) -> Result<(), sqlx::migrate::MigrateError> {
+    sqlx::migrate!("./migrations").run(pool).await
+}
+
+// arbitrary key shared by every instance of the crawler
+const CRAWL_ADVISORY_LOCK: i64 = 4_242_001;
+
+/// Session level lock held by the crawl running on `connection`, so that two machines never crawl at the same time
+pub async fn try_lock_crawl(connection: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let locked: (bool,) = sqlx::query_as("SELECT pg_try_advisory_lock($1)")
+        .bind(CRAWL_ADVISORY_LOCK)
+        .fetch_one(connection)
+        .await?;
+
+    Ok(locked.0)
+}
+
+pub async fn unlock_crawl(connection: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(CRAWL_ADVISORY_LOCK)
+        .execute(connection)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn add_nft(executor: impl PgExecutor<'_>, character: &Nft) -> Result<(), sqlx::Error> {
     let query = r#"
-      INSERT INTO nft (character_name, seq, transport_id, nft_id, sealed_dt, class, lvl, power_score, price, mirage_score, mira_x, reinforce, trade_type, world_name, stats, skills, training, buildings, assets, potentials, holy_stuff, codex, equip_items, tickets, inventory_id, succession_id, spirits_id, magic_orb_id, magic_stone_id, mystical_piece_id)
-      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30)
+      INSERT INTO nft (character_name, seq, transport_id, nft_id, sealed_dt, class, lvl, power_score, price, mirage_score, mira_x, reinforce, trade_type, world_name, stats, skills, training, buildings, assets, potentials, holy_stuff, codex, equip_items, tickets, inventory_id, succession_id, spirits_id, magic_orb_id, magic_stone_id, mystical_piece_id, missing_sections)
+      VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30, $31)
     "#;
 
     let stats_json_string = serde_json::to_string(&character.stats).unwrap();
@@ -53,14 +89,315 @@ pub async fn add_nft(pool: &Pool<Postgres>, character: &Nft) -> Result<(), sqlx:
         .bind(character.magic_orb_id)
         .bind(character.magic_stone_id)
         .bind(character.mystical_piece_id)
+        .bind(&character.missing_sections)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Sections of a character stored in their own tables and referenced by the nft row, `None` when
+/// the section could not be fetched
+pub struct NftSections<'a> {
+    pub inventory: Option<&'a InventoryResponse>,
+    pub succession: Option<&'a SuccessionResponse>,
+    pub spirits: Option<&'a SpiritsObject>,
+    pub magic_orb: Option<&'a MagicOrbResponse>,
+    pub magic_stone: Option<&'a MagicStoneResponseObject>,
+    pub mystical_piece: Option<&'a MysticalPieceResponseObject>,
+}
+
+/// Inserts a new character and its sections in a single transaction, nothing is kept if any insert fails
+pub async fn add_nft_with_sections(
+    pool: &Pool<Postgres>,
+    character: &mut Nft,
+    sections: &NftSections<'_>,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let stored = delete_nft(&mut transaction, character.transport_id).await?;
+
+    if let Some(inventory) = sections.inventory {
+        character.inventory_id = Some(add_inventory(&mut *transaction, inventory).await?);
+    }
+    if let Some(succession) = sections.succession {
+        character.succession_id = Some(add_succession(&mut *transaction, succession).await?);
+    }
+    if let Some(spirits) = sections.spirits {
+        character.spirits_id = Some(add_spirits(&mut *transaction, spirits).await?);
+    }
+    if let Some(magic_orb) = sections.magic_orb {
+        character.magic_orb_id = Some(add_magic_orb(&mut *transaction, magic_orb).await?);
+    }
+    if let Some(magic_stone) = sections.magic_stone {
+        character.magic_stone_id = Some(add_magic_stone(&mut *transaction, magic_stone).await?);
+    }
+    if let Some(mystical_piece) = sections.mystical_piece {
+        character.mystical_piece_id =
+            Some(add_mystical_piece(&mut *transaction, mystical_piece).await?);
+    }
+    add_nft(&mut *transaction, character).await?;
+
+    if let Some(stored) = stored {
+        // a character crawled again keeps its market history, only a new price is dated now
+        let query = r#"
+          UPDATE nft
+          SET listed_at = $2::timestamptz,
+              price_updated_at = CASE WHEN price = $3 THEN $4::timestamptz ELSE price_updated_at END,
+              status = $5,
+              delisted_at = $6::timestamptz
+          WHERE transport_id = $1
+        "#;
+        sqlx::query(query)
+            .bind(character.transport_id)
+            .bind(stored.listed_at)
+            .bind(stored.price)
+            .bind(stored.price_updated_at)
+            .bind(stored.status)
+            .bind(stored.delisted_at)
+            .execute(&mut *transaction)
+            .await?;
+    }
+
+    clear_crawl_failures(&mut *transaction, character.transport_id).await?;
+
+    transaction.commit().await
+}
+
+/// Market history of a stored character, timestamps as text
+#[derive(sqlx::FromRow)]
+struct StoredNft {
+    listed_at: String,
+    price: i32,
+    price_updated_at: String,
+    status: String,
+    delisted_at: Option<String>,
+}
+
+/// Deletes a stored character and its sections, returning its market history
+async fn delete_nft(
+    connection: &mut PgConnection,
+    transport_id: i32,
+) -> Result<Option<StoredNft>, sqlx::Error> {
+    let query = r#"
+      WITH deleted AS (
+        DELETE FROM nft WHERE transport_id = $1
+        RETURNING listed_at, price, price_updated_at, status, delisted_at, inventory_id, succession_id, spirits_id, magic_orb_id, magic_stone_id, mystical_piece_id
+      ),
+      inventory AS (DELETE FROM inventory WHERE id IN (SELECT inventory_id FROM deleted)),
+      succession AS (DELETE FROM succession WHERE id IN (SELECT succession_id FROM deleted)),
+      spirits AS (DELETE FROM spirits WHERE id IN (SELECT spirits_id FROM deleted)),
+      magic_orb AS (DELETE FROM magic_orb WHERE id IN (SELECT magic_orb_id FROM deleted)),
+      magic_stone AS (DELETE FROM magic_stone WHERE id IN (SELECT magic_stone_id FROM deleted)),
+      mystical_piece AS (DELETE FROM mystical_piece WHERE id IN (SELECT mystical_piece_id FROM deleted))
+      SELECT listed_at::text, price, price_updated_at::text, status, delisted_at::text FROM deleted
+    "#;
+
+    sqlx::query_as(query)
+        .bind(transport_id)
+        .fetch_optional(connection)
+        .await
+}
+
+/// Records why a character, or one of its sections, could not be crawled
+pub async fn add_crawl_failure(
+    pool: &Pool<Postgres>,
+    listing: &serde_json::Value,
+    section: &str,
+    error: &CrawlError,
+) -> Result<(), sqlx::Error> {
+    let query = r#"
+      INSERT INTO crawl_failures (transport_id, seq, section, error, payload, listing)
+      VALUES ($1, $2, $3, $4, $5, $6)
+      ON CONFLICT (transport_id, section) DO UPDATE
+      SET seq = excluded.seq,
+          error = excluded.error,
+          payload = excluded.payload,
+          listing = excluded.listing,
+          attempts = crawl_failures.attempts + 1,
+          failed_at = now()
+    "#;
+
+    sqlx::query(query)
+        .bind(listing["transport_id"].as_i64().unwrap_or_default())
+        .bind(listing["seq"].as_i64().unwrap_or_default() as i32)
+        .bind(section)
+        .bind(error.to_string())
+        .bind(error.payload())
+        .bind(listing)
         .execute(pool)
         .await?;
 
     Ok(())
 }
 
-pub async fn add_inventory(
+pub async fn clear_crawl_failures(
+    executor: impl PgExecutor<'_>,
+    transport_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM crawl_failures WHERE transport_id = $1")
+        .bind(transport_id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+/// Listing entries of every character with a recorded failure
+pub async fn get_crawl_failures(pool: &Pool<Postgres>) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let rows: Vec<(serde_json::Value,)> = sqlx::query_as(
+        "SELECT DISTINCT ON (transport_id) listing FROM crawl_failures ORDER BY transport_id, failed_at DESC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.0).collect())
+}
+
+/// Listing entry of a stored character, or of one recorded in crawl_failures, by `transport_id` or `seq`
+pub async fn get_stored_listing(
+    pool: &Pool<Postgres>,
+    column: &str,
+    value: i64,
+) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    let query = format!(
+        r#"
+      SELECT listing FROM (
+        SELECT
+          jsonb_build_object(
+            'seq', seq, 'transport_id', transport_id, 'nft_id', nft_id, 'sealed_dt', sealed_dt,
+            'character_name', character_name, 'class', class, 'lvl', lvl, 'power_score', power_score,
+            'price', price, 'mirage_score', mirage_score, 'mira_x', mira_x, 'reinforce', reinforce
+          ) AS listing,
+          0 AS source
+        FROM nft WHERE {column} = $1
+        UNION ALL
+        SELECT listing, 1 AS source FROM crawl_failures WHERE {column} = $1
+      ) AS stored
+      ORDER BY source
+      LIMIT 1
+    "#,
+        column = column
+    );
+
+    let row: Option<(serde_json::Value,)> = sqlx::query_as(&query).bind(value).fetch_optional(pool).await?;
+
+    Ok(row.map(|row| row.0))
+}
+
+/// Stored characters with the columns flattened by the export command, ordered by transport_id
+pub async fn get_export_rows(
     pool: &Pool<Postgres>,
+    filter: &ExportFilter,
+) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let query = r#"
+      SELECT
+        jsonb_build_object(
+          'transport_id', transport_id, 'seq', seq, 'nft_id', nft_id, 'character_name', character_name,
+          'class', class, 'lvl', lvl, 'power_score', power_score, 'price', price, 'world_name', world_name,
+          'status', status, 'stats', stats, 'skills', skills, 'training', training, 'buildings', buildings,
+          'potentials', potentials, 'codex', codex
+        )
+      FROM nft
+      WHERE ($1::smallint IS NULL OR class = $1)
+        AND ($2::smallint IS NULL OR lvl >= $2)
+        AND ($3::smallint IS NULL OR lvl <= $3)
+        AND ($4::text IS NULL OR world_name = $4)
+      ORDER BY transport_id
+    "#;
+
+    let rows: Vec<(serde_json::Value,)> = sqlx::query_as(query)
+        .bind(filter.class)
+        .bind(filter.lev_min)
+        .bind(filter.lev_max)
+        .bind(&filter.world)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.into_iter().map(|row| row.0).collect())
+}
+
+/// Replaces every row of item_catalog
+pub async fn replace_item_catalog(
+    pool: &Pool<Postgres>,
+    catalog: &[CatalogItem],
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query("DELETE FROM item_catalog")
+        .execute(&mut *transaction)
+        .await?;
+
+    let query = r#"
+      INSERT INTO item_catalog (item_id, name, grade, tier, main_type, sub_type, trade_type)
+      SELECT item_id, name, grade, tier, main_type, sub_type, trade_type
+      FROM jsonb_to_recordset($1) AS item(
+        item_id text, name text, grade integer, tier integer, main_type integer, sub_type integer, trade_type integer
+      )
+    "#;
+
+    sqlx::query(query)
+        .bind(serde_json::json!(catalog))
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await
+}
+
+pub async fn add_price_history(pool: &Pool<Postgres>, character: &Nft) -> Result<(), sqlx::Error> {
+    let query = r#"
+      INSERT INTO nft_price_history (transport_id, seq, price)
+      VALUES ($1, $2, $3)
+    "#;
+
+    sqlx::query(query)
+        .bind(character.transport_id)
+        .bind(character.seq)
+        .bind(character.price)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Returns whether the price changed
+pub async fn update_nft_price(pool: &Pool<Postgres>, character: &Nft) -> Result<bool, sqlx::Error> {
+    let query = r#"
+      UPDATE nft
+      SET seq = $3,
+          price = $2,
+          price_updated_at = CASE WHEN nft.price <> $2 THEN now() ELSE nft.price_updated_at END,
+          status = 'listed',
+          delisted_at = NULL
+      FROM nft AS previous
+      WHERE nft.transport_id = $1 AND previous.id = nft.id
+      RETURNING previous.price <> $2
+    "#;
+
+    let price_changed: Option<(bool,)> = sqlx::query_as(query)
+        .bind(character.transport_id)
+        .bind(character.price)
+        .bind(character.seq)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(price_changed.is_some_and(|row| row.0))
+}
+
+pub async fn mark_delisted(pool: &Pool<Postgres>, listed_seqs: &[i32]) -> Result<u64, sqlx::Error> {
+    let query = r#"
+      UPDATE nft
+      SET status = 'delisted', delisted_at = now()
+      WHERE status = 'listed' AND NOT (seq = ANY($1))
+    "#;
+
+    let result = sqlx::query(query).bind(listed_seqs).execute(pool).await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn add_inventory(
+    executor: impl PgExecutor<'_>,
     inventory_response: &InventoryResponse,
 ) -> Result<i64, sqlx::Error> {
     let query = r#"
@@ -75,16 +412,16 @@ pub async fn add_inventory(
     let id: (i64,) = sqlx::query_as(query)
         .bind(json_items)
         .bind(json_craft_materials)
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
     Ok(id.0)
 }
 
 pub async fn add_succession(
-    pool: Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     succession_response: &SuccessionResponse,
-) -> anyhow::Result<i64> {
+) -> Result<i64, sqlx::Error> {
     let query = r#"
         INSERT INTO succession (succession)
         VALUES ($1)
@@ -95,16 +432,16 @@ pub async fn add_succession(
 
     let id: (i64,) = sqlx::query_as(query)
         .bind(json_items)
-        .fetch_one(&pool)
+        .fetch_one(executor)
         .await?;
 
     Ok(id.0)
 }
 
 pub async fn add_spirits(
-    pool: Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     spirits_response: &SpiritsObject,
-) -> anyhow::Result<i64> {
+) -> Result<i64, sqlx::Error> {
     let query = r#"
         INSERT INTO spirits (equip, inven)
         VALUES ($1, $2)
@@ -117,16 +454,16 @@ pub async fn add_spirits(
     let id: (i64,) = sqlx::query_as(query)
         .bind(equip)
         .bind(inven)
-        .fetch_one(&pool)
+        .fetch_one(executor)
         .await?;
 
     Ok(id.0)
 }
 
 pub async fn add_magic_orb(
-    pool: Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     magic_orb_response: &MagicOrbResponse,
-) -> anyhow::Result<i64> {
+) -> Result<i64, sqlx::Error> {
     let query = r#"
         INSERT INTO magic_orb (equip_item, active_deck)
         VALUES ($1, $2)
@@ -138,15 +475,16 @@ pub async fn add_magic_orb(
     let id: (i64,) = sqlx::query_as(query)
         .bind(equip_item)
         .bind(magic_orb_response.data.active_deck)
-        .fetch_one(&pool)
+        .fetch_one(executor)
         .await?;
 
     Ok(id.0)
 }
+
 pub async fn add_magic_stone(
-    pool: Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     magic_stone_response: &MagicStoneResponseObject,
-) -> anyhow::Result<i64> {
+) -> Result<i64, sqlx::Error> {
     let query = r#"
         INSERT INTO magic_stone (equip_item, active_deck)
         VALUES ($1, $2)
@@ -158,16 +496,16 @@ pub async fn add_magic_stone(
     let id: (i64,) = sqlx::query_as(query)
         .bind(equip_item)
         .bind(magic_stone_response.active_deck)
-        .fetch_one(&pool)
+        .fetch_one(executor)
         .await?;
 
     Ok(id.0)
 }
 
 pub async fn add_mystical_piece(
-    pool: Pool<Postgres>,
+    executor: impl PgExecutor<'_>,
     mystical_piece_response: &MysticalPieceResponseObject,
-) -> anyhow::Result<i64> {
+) -> Result<i64, sqlx::Error> {
     let query = r#"
         INSERT INTO mystical_piece (equip_item, active_deck)
         VALUES ($1, $2)
@@ -179,8 +517,108 @@ pub async fn add_mystical_piece(
     let id: (i64,) = sqlx::query_as(query)
         .bind(equip_item)
         .bind(mystical_piece_response.active_deck)
-        .fetch_one(&pool)
+        .fetch_one(executor)
         .await?;
 
     Ok(id.0)
 }
+
+pub async fn add_localized_names(
+    pool: &Pool<Postgres>,
+    language_code: &str,
+    localized_names: &[LocalizedName],
+) -> Result<(), sqlx::Error> {
+    let query = r#"
+        INSERT INTO localized_name (kind, key, language_code, english_name, name)
+        SELECT kind, key, $5, english_name, name
+        FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[]) AS t (kind, key, english_name, name)
+        ON CONFLICT (kind, key, language_code)
+        DO UPDATE SET english_name = excluded.english_name, name = excluded.name, updated_at = now()
+    "#;
+
+    let kinds: Vec<&str> = localized_names.iter().map(|n| n.kind).collect();
+    let keys: Vec<&str> = localized_names.iter().map(|n| n.key.as_str()).collect();
+    let english_names: Vec<Option<&str>> =
+        localized_names.iter().map(|n| n.english_name.as_deref()).collect();
+    let names: Vec<&str> = localized_names.iter().map(|n| n.name.as_str()).collect();
+
+    sqlx::query(query)
+        .bind(kinds)
+        .bind(keys)
+        .bind(english_names)
+        .bind(names)
+        .bind(language_code)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Starts the report of a crawl, `final_page` is `None` when the listing is crawled until exhausted
+pub async fn start_crawl_run(
+    pool: &Pool<Postgres>,
+    initial_page: u32,
+    final_page: Option<u32>,
+) -> Result<i64, sqlx::Error> {
+    let id: (i64,) = sqlx::query_as(
+        "INSERT INTO crawl_runs (initial_page, final_page) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(initial_page as i32)
+    .bind(final_page.map(|page| page as i32))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(id.0)
+}
+
+pub async fn finish_crawl_run(
+    pool: &Pool<Postgres>,
+    id: i64,
+    summary: &CrawlSummary,
+    requests: &HashMap<String, u64>,
+    duration: Duration,
+) -> Result<(), sqlx::Error> {
+    let query = r#"
+      UPDATE crawl_runs
+      SET finished_at = now(),
+          pages_fetched = $2,
+          failed_pages = $3,
+          new_characters = $4,
+          updated_characters = $5,
+          skipped_characters = $6,
+          failed_characters = $7,
+          requests = $8,
+          duration_ms = $9
+      WHERE id = $1
+    "#;
+
+    sqlx::query(query)
+        .bind(id)
+        .bind(summary.pages_fetched as i32)
+        .bind(summary.failed_pages as i32)
+        .bind(summary.new_characters as i32)
+        .bind(summary.updated_characters as i32)
+        .bind(summary.skipped_characters as i32)
+        .bind(summary.failed_characters as i32)
+        .bind(serde_json::json!(requests))
+        .bind(duration.as_millis() as i64)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Most recent crawl reports, newest first
+pub async fn get_crawl_runs(
+    pool: &Pool<Postgres>,
+    limit: u32,
+) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let rows: Vec<(serde_json::Value,)> = sqlx::query_as(
+        "SELECT to_jsonb(r) || jsonb_build_object('started_at', to_char(started_at, 'YYYY-MM-DD HH24:MI:SS')) FROM crawl_runs r ORDER BY id DESC LIMIT $1",
+    )
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.0).collect())
+}
@@ -0,0 +1,68 @@
+use thiserror::Error;
+
+/// Why a request to the Mir4 API, or storing its result, failed
+#[derive(Debug, Error)]
+pub enum CrawlError {
+    #[error("request failed: {0}")]
+    Network(#[from] reqwest_middleware::Error),
+    #[error("unexpected response: {message}")]
+    Decode { message: String, payload: String },
+    #[error("api is under maintenance: {}", message.as_deref().unwrap_or("no message"))]
+    Maintenance { message: Option<String>, payload: String },
+    /// Usually a character that left the market while it was crawled
+    #[error("api found nothing: {}", message.as_deref().unwrap_or("no message"))]
+    NotFound { message: Option<String>, payload: String },
+    #[error("api is rate limiting requests: {}", message.as_deref().unwrap_or("no message"))]
+    RateLimited { message: Option<String>, payload: String },
+    #[error("api answered with code {code}: {}", message.as_deref().unwrap_or("no message"))]
+    Api {
+        code: i64,
+        message: Option<String>,
+        payload: String,
+    },
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("could not write output: {0}")]
+    Output(#[from] std::io::Error),
+}
+
+impl CrawlError {
+    /// Raw response that could not be used, if any
+    pub fn payload(&self) -> Option<&str> {
+        match self {
+            CrawlError::Decode { payload, .. }
+            | CrawlError::Maintenance { payload, .. }
+            | CrawlError::NotFound { payload, .. }
+            | CrawlError::RateLimited { payload, .. }
+            | CrawlError::Api { payload, .. } => Some(payload),
+            CrawlError::Network(_) | CrawlError::Database(_) | CrawlError::Output(_) => None,
+        }
+    }
+
+    pub fn decode(message: impl ToString, payload: impl ToString) -> Self {
+        CrawlError::Decode {
+            message: message.to_string(),
+            payload: payload.to_string(),
+        }
+    }
+
+    /// Error for a `code` other than 200 in an api answer
+    pub fn from_api(code: i64, message: Option<String>, payload: impl ToString) -> Self {
+        let payload = payload.to_string();
+        let maintenance = message
+            .as_deref()
+            .is_some_and(|message| message.to_lowercase().contains("maintenance"));
+
+        match code {
+            404 => CrawlError::NotFound { message, payload },
+            429 => CrawlError::RateLimited { message, payload },
+            503 => CrawlError::Maintenance { message, payload },
+            _ if maintenance => CrawlError::Maintenance { message, payload },
+            code => CrawlError::Api {
+                code,
+                message,
+                payload,
+            },
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use serde_json::{Map, Value};
+use sqlx::{Pool, Postgres};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::cli::{ExportArgs, ExportFormat};
+use crate::db;
+use crate::ndjson::NdjsonWriter;
+use crate::utils::create_output;
+
+const FIXED_COLUMNS: [&str; 10] = [
+    "transport_id",
+    "seq",
+    "nft_id",
+    "character_name",
+    "class",
+    "lvl",
+    "power_score",
+    "price",
+    "world_name",
+    "status",
+];
+
+// jsonb columns exported as one column per key, e.g. `stats.HP`
+const FLATTENED_COLUMNS: [&str; 5] = ["stats", "skills", "training", "buildings", "potentials"];
+
+/// Writes the stored characters matching the filters, one row per character
+pub async fn export(pool: &Pool<Postgres>, args: &ExportArgs) -> anyhow::Result<()> {
+    let rows: Vec<Map<String, Value>> = db::get_export_rows(pool, &args.filter)
+        .await?
+        .iter()
+        .map(flatten)
+        .collect();
+    let columns = columns(&rows);
+
+    match args.format {
+        ExportFormat::Csv => write_csv(create_output(&args.output)?, &columns, &rows)?,
+        ExportFormat::Ndjson => {
+            let writer = NdjsonWriter::create(&args.output)?;
+            for row in &rows {
+                writer.write(row)?;
+            }
+        }
+        ExportFormat::Parquet => write_parquet(create_output(&args.output)?, &columns, &rows)?,
+    }
+
+    tracing::info!("{} characters exported with {} columns", rows.len(), columns.len());
+
+    Ok(())
+}
+
+fn flatten(row: &Value) -> Map<String, Value> {
+    let mut flat = Map::new();
+    for column in FIXED_COLUMNS {
+        flat.insert(column.to_string(), row[column].clone());
+    }
+
+    for column in FLATTENED_COLUMNS {
+        if let Some(object) = row[column].as_object() {
+            for (key, value) in object {
+                flat.insert(format!("{column}.{key}"), number_or_string(value));
+            }
+        }
+    }
+
+    flat.insert("codex.completed".to_string(), row["codex"]["completed"].clone());
+    flat.insert("codex.in_progress".to_string(), row["codex"]["in_progress"].clone());
+
+    flat
+}
+
+// the api sends some levels as strings, e.g. training's "Constitution": "20"
+fn number_or_string(value: &Value) -> Value {
+    match value.as_str().and_then(|text| text.parse::<i64>().ok()) {
+        Some(number) => Value::from(number),
+        None => value.clone(),
+    }
+}
+
+/// Fixed columns first, then every flattened key seen in any row in alphabetical order
+fn columns(rows: &[Map<String, Value>]) -> Vec<String> {
+    let flattened: BTreeSet<&String> = rows
+        .iter()
+        .flat_map(|row| row.keys())
+        .filter(|column| !FIXED_COLUMNS.contains(&column.as_str()))
+        .collect();
+
+    FIXED_COLUMNS
+        .iter()
+        .map(|column| column.to_string())
+        .chain(flattened.into_iter().cloned())
+        .collect()
+}
+
+fn cell(value: Option<&Value>) -> Option<String> {
+    match value {
+        None | Some(Value::Null) => None,
+        Some(Value::String(text)) => Some(text.clone()),
+        Some(value) => Some(value.to_string()),
+    }
+}
+
+fn write_csv(
+    output: Box<dyn Write + Send>,
+    columns: &[String],
+    rows: &[Map<String, Value>],
+) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record(columns)?;
+    for row in rows {
+        writer.write_record(
+            columns
+                .iter()
+                .map(|column| cell(row.get(column)).unwrap_or_default()),
+        )?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Integer columns are Int64, other numeric columns Float64 and everything else Utf8
+fn column_type(rows: &[Map<String, Value>], column: &str) -> DataType {
+    let values = || {
+        rows.iter()
+            .filter_map(|row| row.get(column))
+            .filter(|value| !value.is_null())
+    };
+
+    if values().all(Value::is_i64) {
+        DataType::Int64
+    } else if values().all(Value::is_number) {
+        DataType::Float64
+    } else {
+        DataType::Utf8
+    }
+}
+
+fn write_parquet(
+    output: Box<dyn Write + Send>,
+    columns: &[String],
+    rows: &[Map<String, Value>],
+) -> anyhow::Result<()> {
+    let mut fields = Vec::new();
+    let mut arrays: Vec<ArrayRef> = Vec::new();
+    for column in columns {
+        let data_type = column_type(rows, column);
+        let values = rows.iter().map(|row| row.get(column));
+        let array: ArrayRef = match data_type {
+            DataType::Int64 => Arc::new(Int64Array::from(
+                values.map(|value| value.and_then(Value::as_i64)).collect::<Vec<_>>(),
+            )),
+            DataType::Float64 => Arc::new(Float64Array::from(
+                values.map(|value| value.and_then(Value::as_f64)).collect::<Vec<_>>(),
+            )),
+            _ => Arc::new(StringArray::from(values.map(cell).collect::<Vec<_>>())),
+        };
+        fields.push(Field::new(column, data_type, true));
+        arrays.push(array);
+    }
+
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+
+    let mut writer = ArrowWriter::try_new(output, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    Ok(())
+}
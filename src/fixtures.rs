@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+
+pub enum FixtureMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Writes every response body to disk, or serves previously written bodies instead of hitting
+/// the network. Bodies are stored as `<dir>/<endpoint path>/<query>.json`.
+pub struct FixtureMiddleware {
+    pub mode: FixtureMode,
+}
+
+impl FixtureMiddleware {
+    fn fixture_path(dir: &Path, request: &Request) -> PathBuf {
+        let url = request.url();
+        let query: String = url
+            .query()
+            .unwrap_or("index")
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '=' | '&' | '.' | '_' | '-' => c,
+                _ => '_',
+            })
+            .collect();
+
+        dir.join(url.path().trim_matches('/'))
+            .join(format!("{query}.json"))
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for FixtureMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        match &self.mode {
+            FixtureMode::Replay(dir) => {
+                let path = Self::fixture_path(dir, &req);
+                let body = tokio::fs::read(&path).await.map_err(|error| {
+                    reqwest_middleware::Error::Middleware(anyhow::anyhow!(
+                        "no recorded response for {} at {}: {}",
+                        req.url(),
+                        path.display(),
+                        error
+                    ))
+                })?;
+
+                Ok(Response::from(http::Response::new(body)))
+            }
+            FixtureMode::Record(dir) => {
+                let path = Self::fixture_path(dir, &req);
+                let response = next.run(req, extensions).await?;
+
+                let status = response.status();
+                let headers = response.headers().clone();
+                let body = response.bytes().await?;
+
+                if let Err(error) = write_fixture(&path, &body).await {
+                    tracing::error!("Fail to record response at {}: {}", path.display(), error);
+                }
+
+                let mut recorded = http::Response::new(body);
+                *recorded.status_mut() = status;
+                *recorded.headers_mut() = headers;
+
+                Ok(Response::from(recorded))
+            }
+        }
+    }
+}
+
+async fn write_fixture(path: &Path, body: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, body).await
+}
@@ -1,33 +1,35 @@
 use clap::Parser;
-use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_middleware::ClientBuilder;
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
-use responses::nft::{Nft, NftListResponse};
 use sqlx::{Pool, Postgres};
 use std::fs;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::Mutex;
-use tokio::task::{JoinError, JoinSet};
-
-use crate::cli::Cli;
-use crate::responses::magic_orb::get_nft_magic_orb;
-use crate::responses::magic_stone::get_nft_magic_stone;
-use crate::responses::mystical_piece::get_nft_mystical_piece;
-use crate::responses::spirits::get_nft_spirits;
-use crate::responses::succession::get_nft_succession;
-use crate::responses::ticket::get_nft_tickets;
-use crate::responses::{
-    assets::get_nft_assets, building::get_nft_buildings, codex::get_nft_codex,
-    holy_stuff::get_nft_holy_stuff, inventory::get_nft_inventory, potentials::get_nft_potentials,
-    skills::get_nft_skills, stats::get_nft_stats, summary::get_nft_summary,
-    training::get_nft_training,
-};
+use std::time::Duration;
+
+use crate::circuit::CircuitBreaker;
+use crate::cli::{ApiOptions, CatalogArgs, Cli, Command, CrawlArgs};
+use crate::fixtures::{FixtureMiddleware, FixtureMode};
+use crate::metrics::RequestCounter;
+use crate::responses::envelope::EnvelopeStatus;
+use crate::responses::item_detail::ItemDetailCache;
+use crate::throttle::RateLimiter;
+use crate::tradable::TradableCatalog;
+use crate::ndjson::NdjsonWriter;
 use crate::utils::AppState;
-use crate::utils::{get_response, nft_description_error};
 
+mod circuit;
 mod cli;
+mod crawler;
 mod db;
+mod error;
+mod export;
+mod fixtures;
+mod metrics;
+mod ndjson;
 mod responses;
+mod server;
+mod throttle;
+mod tradable;
 mod utils;
 
 #[tokio::main(flavor = "multi_thread")]
@@ -35,32 +37,79 @@ async fn main() -> anyhow::Result<()> {
 
     let cli = Cli::parse();
 
-    let mut data = fs::read_to_string("list.json")?;
-
     if cli.local {
         dotenvy::dotenv().expect(".env file not found");
-        data = fs::read_to_string("../dump_trade_items/list.json")?;
     }
 
-    tracing_subscriber::fmt().init();
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+
+    // commands that do not need the database
+    let command = match cli.command {
+        Command::Catalog(args) if !args.load => return catalog(None, args).await,
+        Command::CatalogDiff { old, new, json } => {
+            let diff = dump_trade_items::diff_catalogs(
+                &dump_trade_items::read_catalog(&old)?,
+                &dump_trade_items::read_catalog(&new)?,
+            );
+            print!("{}", diff.changelog());
+            if let Some(path) = json {
+                fs::write(&path, serde_json::to_string_pretty(&diff)?)?;
+            }
+            return Ok(());
+        }
+        Command::Crawl(args) if args.ndjson.is_some() => return crawl(None, args).await,
+        Command::Fetch(args) if !args.save => {
+            return crawler::fetch_character(create_app_state(None, args.api.clone())?, &args).await
+        }
+        command => command,
+    };
 
-    let retry_policy = ExponentialBackoff::builder().build_with_max_retries(1);
-    let basic_client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
+    let pool = db::create_pool().await?;
+    db::migrate(&pool).await?;
 
-    let bindings = Arc::new(Mutex::new(AppState {
-        db: db::create_pool().await?,
-        client: ClientBuilder::new(basic_client)
-            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-            .build(),
-    }));
-    let app_state = bindings.lock().await;
+    match command {
+        Command::Crawl(args) => crawl(Some(pool), args).await,
+        Command::Serve { port } => server::serve(pool, port).await,
+        Command::Migrate => {
+            tracing::info!("Database is up to date");
+            Ok(())
+        }
+        Command::RetryFailures { api } => {
+            crawler::retry_failures(create_app_state(Some(pool), api)?).await
+        }
+        Command::Fetch(args) => {
+            crawler::fetch_character(create_app_state(Some(pool), args.api.clone())?, &args).await
+        }
+        Command::Export(args) => export::export(&pool, &args).await,
+        Command::Runs { count } => crawler::print_runs(&pool, count).await,
+        Command::Catalog(args) => catalog(Some(pool), args).await,
+        Command::CatalogDiff { .. } => unreachable!("catalog-diff runs before connecting to the database"),
+    }
+}
 
-    let now = Instant::now();
+async fn catalog(pool: Option<Pool<Postgres>>, args: CatalogArgs) -> anyhow::Result<()> {
+    let items = dump_trade_items::read_items(&args.items)?;
+    dump_trade_items::write_trade_types(&items, &args.output)?;
+    tracing::info!("Tradable item list written to {}", args.output.display());
 
-    let traddable_list: serde_json::Value =
-        serde_json::from_str(&data).expect("list.json file was not found");
+    let catalog = dump_trade_items::item_catalog(&items);
+    if let Some(path) = &args.catalog {
+        dump_trade_items::write_item_catalog(&catalog, path)?;
+        tracing::info!("Item catalog written to {}", path.display());
+    }
+    if let Some(pool) = pool {
+        db::replace_item_catalog(&pool, &catalog).await?;
+        tracing::info!("{} items loaded into item_catalog", catalog.len());
+    }
+
+    Ok(())
+}
+
+async fn crawl(pool: Option<Pool<Postgres>>, args: CrawlArgs) -> anyhow::Result<()> {
+    let mut app_state = create_app_state(pool, args.api)?;
+    if let Some(path) = &args.ndjson {
+        app_state.ndjson = Some(NdjsonWriter::create(path)?);
+    }
 
     let delete_all_queries = vec![
         "DELETE FROM nft",
@@ -72,271 +121,68 @@ async fn main() -> anyhow::Result<()> {
         "DELETE FROM mystical_piece",
     ];
 
-    if cli.db_drop {
+    if args.db_drop {
         for query in delete_all_queries {
             sqlx::query(query)
-                .execute(&app_state.db.to_owned())
+                .execute(app_state.pool()?)
                 .await?;
         }
     }
 
-    let mut join_set = JoinSet::new();
-
-    for i in cli.initial_page..cli.final_page + 1 {
-        join_set.spawn(retrieve_and_save_nft(
-            app_state.client.to_owned(),
-            i,
-            app_state.db.to_owned(),
-            traddable_list.clone(),
-        ));
-    }
-
-    while let Some(res) = join_set.join_next().await {
-        let out = res?;
-        if let Err(err) = out { tracing::error!("Error spawning NFT list: {}", err) }
-    }
-
-    let elapsed = now.elapsed();
-    tracing::info!("retrieve_and_save_nft function time: {:#?}", elapsed);
-
-    Ok(())
-}
-
-async fn retrieve_and_save_nft(
-    client: ClientWithMiddleware,
-    page_index: u8,
-    database: Pool<Postgres>,
-    traddable_list: serde_json::Value,
-) -> anyhow::Result<()> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/lists?listType=sale&class=0&levMin=0&levMax=0&powerMin=0&powerMax=0&priceMin=0&priceMax=0&sort=latest&page={page}&languageCode=en",
-        page = page_index
-    );
-
-    let response_json: NftListResponse = get_response(&client, request_url).await.unwrap();
-
-    let nft_list = serde_json::to_value(response_json.data.lists)?;
-
-    let list = match nft_list {
-        serde_json::Value::Array(arr) => arr,
-        _ => Vec::new(),
-    };
-
-    let tasks: Vec<_> = list
-        .into_iter()
-        .map(|character| {
-            tokio::spawn(dump_nft(
-                character,
-                database.clone(),
-                client.clone(),
-                traddable_list.clone(),
-            ))
-        })
-        .collect();
-
-    for task in tasks {
-        task.await?
-            .unwrap_or_else(|error| tracing::error!("Error dumping nft: {:#?}", error));
+    if args.daemon.daemon {
+        return crawler::run_daemon(app_state, &args.crawl, &args.daemon).await;
     }
 
-    Ok(())
+    crawler::run_crawl(app_state, &args.crawl).await
 }
 
-async fn dump_nft(
-    nft_data: serde_json::Value,
-    pool: Pool<Postgres>,
-    client: ClientWithMiddleware,
-    tradable_list: serde_json::Value,
-) -> anyhow::Result<(), JoinError> {
-    let mut character: Nft = serde_json::from_value(nft_data.clone()).unwrap_or_else(|_| { panic!("{}", nft_description_error("Fail to get serde_json value from nft", nft_data.clone())) });
+fn create_app_state(pool: Option<Pool<Postgres>>, api: ApiOptions) -> anyhow::Result<AppState> {
+    utils::set_api_base_url(&api.api_url);
 
-    let db_transport_id: (bool,) = sqlx::query_as(
-        "
-      select
-          exists (
-            select
-              1
-            from
-              nft
-            where
-              transport_id = $1
-      );",
-    )
-    .bind(character.transport_id)
-    .fetch_one(&pool)
-    .await
-    .unwrap();
+    let tradable = TradableCatalog::load(&api.list)?;
 
-    if db_transport_id.0 {
-        tracing::info!(
-            "transport_id: {} exist in the database",
-            character.transport_id
-        );
-        return Ok(());
+    let retry_policy = ExponentialBackoff::builder()
+        .retry_bounds(
+            Duration::from_secs(api.retry_min_delay),
+            Duration::from_secs(api.retry_max_delay.max(api.retry_min_delay)),
+        )
+        .build_with_max_retries(api.retries);
+    let mut basic_client = reqwest::Client::builder().danger_accept_invalid_certs(!api.verify_certs);
+    if api.timeout > 0 {
+        basic_client = basic_client.timeout(Duration::from_secs(api.timeout));
     }
-
-    let nft_inventory = tokio::spawn(get_nft_inventory(
-        character.transport_id,
-        client.clone(),
-        tradable_list.clone(),
-    ))
-    .await
-    .unwrap_or_else(|_| { panic!("{}", nft_description_error(
-        "Fail to get nft inventory",
-        nft_data.clone(),
-    )) })
-    .unwrap();
-
-    let succession = tokio::spawn(get_nft_succession(
-        character.transport_id,
-        client.clone(),
-        character.class,
-        nft_inventory.clone().inventory,
-    ))
-    .await
-    .unwrap_or_else(|_| { panic!("{}", nft_description_error(
-        "Fail to get nft succession",
-        nft_data.clone(),
-    )) })
-    .unwrap();
-    let spirits = tokio::spawn(get_nft_spirits(character.transport_id, client.clone()))
-        .await
-        .unwrap_or_else(|_| { panic!("{}", nft_description_error(
-            "Fail to get nft spirits",
-            nft_data.clone(),
-        )) })
-        .unwrap();
-    let magic_orb = tokio::spawn(get_nft_magic_orb(character.transport_id, client.clone()))
-        .await
-        .unwrap_or_else(|_| { panic!("{}", nft_description_error(
-            "Fail to get nft magic_orb",
-            nft_data.clone(),
-        )) })
-        .unwrap();
-    let magic_stone = tokio::spawn(get_nft_magic_stone(
-        character.transport_id,
-        character.class,
-        client.clone(),
-        nft_inventory.clone().inventory,
-        tradable_list.clone(),
-    ))
-    .await
-    .unwrap_or_else(|_| { panic!("{}", nft_description_error(
-        "Fail to get nft magic_stone",
-        nft_data.clone(),
-    )) })
-    .unwrap();
-    let mystical_piece = tokio::spawn(get_nft_mystical_piece(
-        character.transport_id,
-        character.class,
-        client.clone(),
-        nft_inventory.clone().inventory,
-        tradable_list.clone(),
-    ))
-    .await
-    .unwrap_or_else(|_| { panic!("{}", nft_description_error(
-        "Fail to get nft mystical_piece",
-        nft_data.clone(),
-    )) })
-    .unwrap();
-
-    character.tickets = tokio::spawn(get_nft_tickets(nft_inventory.clone().inventory))
-        .await
-        .unwrap_or_else(|_| { panic!("{}", nft_description_error(
-            "Fail to get nft tickets",
-            nft_data.clone(),
-        )) })
-        .unwrap();
-
-    let (summary, stats, skills, training, buildings, assets, potentials, holy_stuff, codex) = tokio::join!(
-        tokio::spawn(get_nft_summary(
-            character.seq,
-            character.transport_id,
-            character.class,
-            client.clone(),
-            nft_inventory.clone().inventory,
-            tradable_list.clone()
-        )),
-        tokio::spawn(get_nft_stats(character.transport_id, client.clone())),
-        tokio::spawn(get_nft_skills(
-            character.transport_id,
-            character.class,
-            client.clone()
-        )),
-        tokio::spawn(get_nft_training(character.transport_id, client.clone())),
-        tokio::spawn(get_nft_buildings(character.transport_id, client.clone())),
-        tokio::spawn(get_nft_assets(character.transport_id, client.clone())),
-        tokio::spawn(get_nft_potentials(character.transport_id, client.clone())),
-        tokio::spawn(get_nft_holy_stuff(character.transport_id, client.clone())),
-        tokio::spawn(get_nft_codex(character.transport_id, client.clone()))
-    );
-
-    match (
-        summary, stats, skills, training, buildings, assets, potentials, holy_stuff, codex,
-    ) {
-        (
-            Ok(summary),
-            ..,
-            Ok(stats),
-            Ok(skills),
-            Ok(training),
-            Ok(buildings),
-            Ok(assets),
-            Ok(potentials),
-            Ok(holy_stuff),
-            Ok(codex),
-        ) => {
-            let summary_data = summary.unwrap();
-            character.trade_type = summary_data.trade_type;
-            character.world_name = summary_data.world_name;
-            character.equip_items = summary_data.equip_items;
-
-            character.stats = stats.unwrap();
-            character.skills = skills.unwrap();
-            character.training = training.unwrap();
-            character.buildings = buildings.unwrap();
-            character.assets = assets.unwrap();
-            character.potentials = potentials.unwrap();
-            character.holy_stuff = holy_stuff.unwrap();
-            character.codex = codex.unwrap();
-        }
-        (Err(err), _, _, _, _, _, _, _, _)
-        | (_, Err(err), _, _, _, _, _, _, _)
-        | (_, _, Err(err), _, _, _, _, _, _)
-        | (_, _, _, Err(err), _, _, _, _, _)
-        | (_, _, _, _, Err(err), _, _, _, _)
-        | (_, _, _, _, _, Err(err), _, _, _)
-        | (_, _, _, _, _, _, Err(err), _, _)
-        | (_, _, _, _, _, _, _, Err(err), _)
-        | (_, _, _, _, _, _, _, _, Err(err)) => {
-            tracing::error!("Error joining nft_creation auxiliary tasks {:#?}", err);
-        }
+    let basic_client = basic_client.build()?;
+
+    let mut client_builder = ClientBuilder::new(basic_client);
+    if let Some(dir) = api.record {
+        client_builder = client_builder.with(FixtureMiddleware {
+            mode: FixtureMode::Record(dir),
+        });
+    } else if let Some(dir) = api.replay {
+        client_builder = client_builder.with(FixtureMiddleware {
+            mode: FixtureMode::Replay(dir),
+        });
     }
 
-    tracing::info!(
-        "Dumping character with the name of {:#?}...",
-        character.character_name
-    );
+    let requests = RequestCounter::default();
 
-    character.inventory_id = db::add_inventory(&pool.clone(), &nft_inventory)
-        .await
-        .unwrap();
-    character.succession_id = db::add_succession(pool.clone(), &succession).await.unwrap();
-    character.spirits_id = db::add_spirits(pool.clone(), &spirits).await.unwrap();
-    character.magic_orb_id = db::add_magic_orb(pool.clone(), &magic_orb).await.unwrap();
-    character.magic_stone_id = db::add_magic_stone(pool.clone(), &magic_stone)
-        .await
-        .unwrap();
-    character.mystical_piece_id = db::add_mystical_piece(pool.clone(), &mystical_piece)
-        .await
-        .unwrap();
-
-    db::add_nft(&pool.clone().clone(), &character)
-        .await
-        .unwrap_or_else(|_| { tracing::error!("{}", nft_description_error(
-            "Fail to add nft to database",
-            nft_data.clone(),
-        )) });
-
-    Ok(())
+    Ok(AppState {
+        db: pool,
+        ndjson: None,
+        client: client_builder
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .with(RateLimiter::new(api.max_in_flight, api.requests_per_second))
+            // checked right before sending, so requests paced by the limiter also wait for the pause
+            .with(CircuitBreaker::new(
+                api.breaker_threshold,
+                Duration::from_secs(api.breaker_cooldown),
+            ))
+            .with(requests.clone())
+            .with(EnvelopeStatus)
+            .build(),
+        tradable: Arc::new(tradable),
+        item_details: ItemDetailCache::new(api.item_detail_concurrency),
+        languages: api.languages,
+        requests,
+    })
 }
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+
+/// Counts the requests sent to each endpoint, keyed by url path
+#[derive(Clone, Default)]
+pub struct RequestCounter {
+    counts: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl RequestCounter {
+    /// Returns the counts since the last call and starts counting from zero
+    pub fn take(&self) -> HashMap<String, u64> {
+        std::mem::take(&mut *self.counts.lock().unwrap())
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RequestCounter {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        *self
+            .counts
+            .lock()
+            .unwrap()
+            .entry(req.url().path().to_string())
+            .or_default() += 1;
+
+        next.run(req, extensions).await
+    }
+}
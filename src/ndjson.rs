@@ -0,0 +1,29 @@
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::utils::create_output;
+
+/// Writes one JSON document per line, shared by every crawl task
+#[derive(Clone)]
+pub struct NdjsonWriter {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl NdjsonWriter {
+    /// Writes to `path`, or to stdout when it is `-`
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(NdjsonWriter {
+            writer: Arc::new(Mutex::new(create_output(path)?)),
+        })
+    }
+
+    /// Object keys are sorted, so the same character always gives the same line
+    pub fn write(&self, value: &impl Serialize) -> io::Result<()> {
+        let mut line = serde_json::to_vec(&serde_json::to_value(value)?)?;
+        line.push(b'\n');
+
+        self.writer.lock().unwrap().write_all(&line)
+    }
+}
@@ -2,6 +2,7 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{de, Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
+use crate::error::CrawlError;
 use crate::utils::get_response;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -35,13 +36,13 @@ pub struct Assets {
 pub async fn get_nft_assets(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<Assets> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/assets?transportID={transport_id}&languageCode=en",
+) -> Result<Assets, CrawlError> {
+    let request_path = format!(
+        "/nft/character/assets?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: AssetsResponse = get_response(&client, request_url).await?;
+    let response_json: AssetsResponse = get_response(&client, request_path).await?;
 
     Ok(response_json.data)
 }
@@ -2,7 +2,8 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::utils::get_response;
+use crate::error::CrawlError;
+use crate::utils::get_response_with_body;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BuildingResponse {
@@ -20,26 +21,30 @@ pub struct BuildingObject {
 pub async fn get_nft_buildings(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<HashMap<String, i32>> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/building?transportID={transport_id}&languageCode=en",
+) -> Result<HashMap<String, i32>, CrawlError> {
+    let request_path = format!(
+        "/nft/character/building?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: BuildingResponse = get_response(&client, request_url).await?;
+    let (response_json, body): (BuildingResponse, String) = get_response_with_body(&client, request_path).await?;
 
     let building_hashmap: HashMap<String, i32> = response_json
         .data
         .iter()
         .map(|building_object| {
-            let value_as_number = building_object.1.building_level.parse::<i32>().unwrap();
+            let value_as_number = building_object
+                .1
+                .building_level
+                .parse::<i32>()
+                .map_err(|error| CrawlError::decode(format!("{}: {:?}", error, building_object.1.building_level), &body))?;
 
-            (
+            Ok((
                 building_object.1.building_name.clone(),
                 value_as_number,
-            )
+            ))
         })
-        .collect();
+        .collect::<Result<_, CrawlError>>()?;
 
     Ok(building_hashmap)
 }
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 
-use crate::{responses, utils::get_response};
+use crate::{error::CrawlError, responses, utils::get_response_with_body};
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct CodexResponse {
@@ -39,32 +39,39 @@ impl StringOrI32 {
             StringOrI32::String(s) => s.parse().ok(),
         }
     }
+
+    /// The number, or a decode error carrying `payload`, the response it comes from
+    pub fn try_as_i32(&self, payload: &str) -> Result<i32, CrawlError> {
+        self.as_i32()
+            .ok_or_else(|| CrawlError::decode(format!("expected a number: {:?}", self), payload))
+    }
 }
 
 pub async fn get_nft_codex(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<CodexResponse> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/codex?transportID={transport_id}&languageCode=en",
+) -> Result<CodexResponse, CrawlError> {
+    let request_path = format!(
+        "/nft/character/codex?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let mut response_json: CodexResponse = get_response(&client, request_url).await?;
+    let (mut response_json, body): (CodexResponse, String) =
+        get_response_with_body(&client, request_path).await?;
 
     let mut in_progress_total = 0;
     let mut completed_total = 0;
-    response_json.data.iter_mut().for_each(|(_, codex)| {
-        let in_progress_int: i32 = codex.in_progress.as_i32().unwrap();
-        let completed_int: i32 = codex.completed.as_i32().unwrap();
-        let total_count_int: i32 = codex.total_count.as_i32().unwrap();
+    for codex in response_json.data.values_mut() {
+        let in_progress_int: i32 = codex.in_progress.try_as_i32(&body)?;
+        let completed_int: i32 = codex.completed.try_as_i32(&body)?;
+        let total_count_int: i32 = codex.total_count.try_as_i32(&body)?;
 
         in_progress_total += in_progress_int;
         completed_total += completed_int;
         codex.in_progress = responses::codex::StringOrI32::Integer(in_progress_int);
         codex.completed = responses::codex::StringOrI32::Integer(completed_int);
         codex.total_count = responses::codex::StringOrI32::Integer(total_count_int);
-    });
+    }
 
     response_json.in_progress = in_progress_total;
     response_json.completed = completed_total;
@@ -0,0 +1,154 @@
+use http::Extensions;
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::error::CrawlError;
+
+/// Fields every Mir4 API answer shares, `body` is the rest of it, usually `data`. The api answers
+/// errors with a 200 status and its own `code` in the body.
+#[derive(Deserialize, Debug)]
+pub struct ApiEnvelope<T> {
+    #[serde(default)]
+    pub code: Option<i64>,
+    #[serde(default, alias = "msg")]
+    pub message: Option<String>,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+impl<T: DeserializeOwned> ApiEnvelope<T> {
+    /// Parses an answer, the code is checked before `T` since error answers have no `data`
+    pub fn parse(payload: &str) -> Result<Self, CrawlError> {
+        let envelope: ApiEnvelope<Value> =
+            serde_json::from_str(payload).map_err(|error| CrawlError::decode(error, payload))?;
+
+        if let Some(code) = envelope.code.filter(|code| *code != 200) {
+            return Err(CrawlError::from_api(code, envelope.message, payload));
+        }
+
+        Ok(ApiEnvelope {
+            code: envelope.code,
+            message: envelope.message,
+            body: serde_json::from_value(envelope.body)
+                .map_err(|error| CrawlError::decode(error, payload))?,
+        })
+    }
+}
+
+/// Gives answers whose body code is a rate limit or a maintenance the matching HTTP status, so
+/// the rate limiter and circuit breaker, which only look at the status, slow down and pause on
+/// them too. The body is left untouched for `ApiEnvelope::parse`.
+pub struct EnvelopeStatus;
+
+#[async_trait::async_trait]
+impl Middleware for EnvelopeStatus {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let response = next.run(req, extensions).await?;
+        if response.status() != StatusCode::OK {
+            return Ok(response);
+        }
+
+        let (version, headers) = (response.version(), response.headers().clone());
+        let body = response.bytes().await?;
+        let status = match serde_json::from_slice::<ApiEnvelope<IgnoredBody>>(&body) {
+            Ok(ApiEnvelope {
+                code: Some(code),
+                message,
+                ..
+            }) if code != 200 => match CrawlError::from_api(code, message, "") {
+                CrawlError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+                CrawlError::Maintenance { .. } => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::OK,
+            },
+            _ => StatusCode::OK,
+        };
+
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+
+        Ok(Response::from(rebuilt))
+    }
+}
+
+// only the code and message of an answer are read to pick its status
+#[derive(Deserialize)]
+struct IgnoredBody {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Data {
+        data: Vec<i32>,
+    }
+
+    fn parse(payload: &str) -> Result<ApiEnvelope<Data>, CrawlError> {
+        ApiEnvelope::parse(payload)
+    }
+
+    #[test]
+    fn parse_keeps_the_data_of_a_200() {
+        let envelope = parse(r#"{"code":200,"data":[1,2]}"#).unwrap();
+        assert_eq!(envelope.code, Some(200));
+        assert_eq!(envelope.body, Data { data: vec![1, 2] });
+    }
+
+    #[test]
+    fn parse_accepts_a_missing_code() {
+        assert_eq!(parse(r#"{"data":[3]}"#).unwrap().body, Data { data: vec![3] });
+    }
+
+    #[test]
+    fn parse_checks_the_code_before_the_data() {
+        let error = parse(r#"{"code":404,"message":"character not found"}"#).unwrap_err();
+        assert!(matches!(
+            error,
+            CrawlError::NotFound { message: Some(ref message), .. } if message == "character not found"
+        ));
+        assert_eq!(error.payload(), Some(r#"{"code":404,"message":"character not found"}"#));
+    }
+
+    #[test]
+    fn parse_reads_msg_as_the_message() {
+        let error = parse(r#"{"code":900,"msg":"unknown failure"}"#).unwrap_err();
+        assert!(matches!(
+            error,
+            CrawlError::Api { code: 900, message: Some(ref message), .. } if message == "unknown failure"
+        ));
+    }
+
+    #[test]
+    fn parse_reports_missing_data_as_decode() {
+        assert!(matches!(parse(r#"{"code":200}"#), Err(CrawlError::Decode { .. })));
+    }
+
+    #[test]
+    fn from_api_maps_codes() {
+        assert!(matches!(CrawlError::from_api(404, None, ""), CrawlError::NotFound { .. }));
+        assert!(matches!(CrawlError::from_api(429, None, ""), CrawlError::RateLimited { .. }));
+        assert!(matches!(CrawlError::from_api(503, None, ""), CrawlError::Maintenance { .. }));
+        assert!(matches!(
+            CrawlError::from_api(500, None, ""),
+            CrawlError::Api { code: 500, message: None, .. }
+        ));
+    }
+
+    #[test]
+    fn from_api_detects_maintenance_messages() {
+        let message = Some("Server Maintenance until 10:00 UTC".to_string());
+        assert!(matches!(
+            CrawlError::from_api(900, message, ""),
+            CrawlError::Maintenance { .. }
+        ));
+    }
+}
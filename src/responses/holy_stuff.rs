@@ -2,7 +2,8 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
-use crate::utils::get_response;
+use crate::error::CrawlError;
+use crate::utils::get_response_with_body;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct HolyStuffResponse {
@@ -23,34 +24,33 @@ where
 {
     Deserialize::deserialize(d).map(|x: Option<_>| x.unwrap_or("0".to_string()))
 }
-#[derive(Serialize, Deserialize, Debug)]
-pub struct HolyStuff {
-    pub holy_stuff: HashMap<String, String>,
-}
-
 pub async fn get_nft_holy_stuff(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<HashMap<String, i32>> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/holystuff?transportID={transport_id}&languageCode=en",
+) -> Result<HashMap<String, i32>, CrawlError> {
+    let request_path = format!(
+        "/nft/character/holystuff?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: HolyStuffResponse = get_response(&client, request_url).await?;
+    let (response_json, body): (HolyStuffResponse, String) = get_response_with_body(&client, request_path).await?;
 
     let holy_stuff_hashmap: HashMap<String, i32> = response_json
         .data
         .iter()
         .map(|holy_stuff_object| {
-            let value_as_number = holy_stuff_object.1.grade.parse::<i32>().unwrap();
+            let value_as_number = holy_stuff_object
+                .1
+                .grade
+                .parse::<i32>()
+                .map_err(|error| CrawlError::decode(format!("{}: {:?}", error, holy_stuff_object.1.grade), &body))?;
 
-            (
+            Ok((
                 holy_stuff_object.1.holy_stuff_name.clone(),
                 value_as_number,
-            )
+            ))
         })
-        .collect();
+        .collect::<Result<_, CrawlError>>()?;
 
     Ok(holy_stuff_hashmap)
 }
@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::utils::default_bool;
 use crate::utils::default_hashmap;
+use crate::error::CrawlError;
+use crate::tradable::TradableCatalog;
 use crate::utils::get_response;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -45,20 +47,20 @@ pub struct InventoryItem {
 pub async fn get_nft_inventory(
     transport_id: i32,
     client: ClientWithMiddleware,
-    tradable_list: serde_json::Value,
-) -> anyhow::Result<InventoryResponse> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/inven?transportID={transport_id}&languageCode=en",
+    tradable: &TradableCatalog,
+) -> Result<InventoryResponse, CrawlError> {
+    let request_path = format!(
+        "/nft/character/inven?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let mut response_json: InventoryResponse = get_response(&client, request_url).await?;
+    let mut response_json: InventoryResponse = get_response(&client, request_path).await?;
     
     response_json
         .inventory
         .iter_mut()
         .for_each(|i| {
-            if tradable_list[&i.item_id] == 1 {
+            if tradable.is_tradable(&i.item_id) {
                 i.is_tradable = true;
             }
 
@@ -1,6 +1,10 @@
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OnceCell, Semaphore};
 
+use crate::error::CrawlError;
 use crate::utils::get_response;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -8,7 +12,7 @@ pub struct ItemDetailResponse {
     pub data: ItemDetailData,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct ItemDetailData {
     #[serde(alias = "powerScore")]
     pub power_score: i32,
@@ -52,15 +56,66 @@ pub async fn get_item_detail(
     transport_id: &i32,
     class: &i32,
     item_uid: &String,
-) -> anyhow::Result<ItemDetailData> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/itemdetail?transportID={transport_id}&class={class}&itemUID={item_uid}&languageCode=en",
+) -> Result<ItemDetailData, CrawlError> {
+    let request_path = format!(
+        "/nft/character/itemdetail?transportID={transport_id}&class={class}&itemUID={item_uid}&languageCode=en",
         transport_id = transport_id,
         class = class,
         item_uid = item_uid
     );
 
-    let response_json: ItemDetailResponse = get_response(client, request_url).await?;
+    let response_json: ItemDetailResponse = get_response(client, request_path).await?;
 
     Ok(response_json.data)
 }
+
+type DetailCell = Arc<OnceCell<ItemDetailData>>;
+
+/// Item details of the current run keyed by `(transport_id, item_uid)`, shared by every section of
+/// every character. Concurrent lookups of the same item wait for a single request.
+#[derive(Clone)]
+pub struct ItemDetailCache {
+    details: Arc<Mutex<HashMap<(i32, String), DetailCell>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl ItemDetailCache {
+    /// At most `concurrency` item details are requested at the same time
+    pub fn new(concurrency: usize) -> Self {
+        ItemDetailCache {
+            details: Arc::default(),
+            permits: Arc::new(Semaphore::new(concurrency.max(1))),
+        }
+    }
+
+    /// Forgets every detail, called when a new run starts
+    pub fn clear(&self) {
+        self.details.lock().unwrap().clear();
+    }
+
+    /// A failed lookup is not cached, the next caller tries again
+    pub async fn get(
+        &self,
+        client: &ClientWithMiddleware,
+        transport_id: i32,
+        class: i32,
+        item_uid: &str,
+    ) -> Result<ItemDetailData, CrawlError> {
+        let cell = self
+            .details
+            .lock()
+            .unwrap()
+            .entry((transport_id, item_uid.to_string()))
+            .or_default()
+            .clone();
+
+        let detail = cell
+            .get_or_try_init(|| async {
+                let _permit = self.permits.acquire().await.expect("semaphore is never closed");
+                get_item_detail(client, &transport_id, &class, &item_uid.to_string()).await
+            })
+            .await?;
+
+        Ok(detail.clone())
+    }
+}
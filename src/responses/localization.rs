@@ -0,0 +1,124 @@
+use reqwest_middleware::ClientWithMiddleware;
+use std::collections::HashMap;
+
+use crate::utils::get_response;
+
+use super::{
+    building::BuildingResponse, holy_stuff::HolyStuffResponse, inventory::InventoryResponse,
+    skills::SkillsResponse, stats::StatsResponse,
+};
+
+/// Name of a stat, skill, building, holy stuff or item in a given language. `key` does not change
+/// between languages: the icon path of stats, `class:index` of skills and the id of the rest.
+/// `english_name` is the name stored in the nft jsonb columns.
+#[derive(Debug, Clone)]
+pub struct LocalizedName {
+    pub kind: &'static str,
+    pub key: String,
+    pub english_name: Option<String>,
+    pub name: String,
+}
+
+// (kind, id that does not change between languages) -> name
+pub type NamesById = HashMap<(&'static str, String), String>;
+
+/// Names of a character in one language
+pub struct CharacterNames {
+    names: NamesById,
+    // skills have no id, they are only matched by index when their levels are in the same order
+    skill_levels: Vec<String>,
+}
+
+async fn get_names(
+    transport_id: i32,
+    class: i32,
+    client: &ClientWithMiddleware,
+    language_code: &str,
+) -> anyhow::Result<CharacterNames> {
+    let (stats, skills, buildings, holy_stuff, inventory) = tokio::try_join!(
+        get_response::<StatsResponse>(
+            client,
+            format!("/nft/character/stats?transportID={transport_id}&languageCode={language_code}")
+        ),
+        get_response::<SkillsResponse>(
+            client,
+            format!("/nft/character/skills?transportID={transport_id}&class={class}&languageCode={language_code}")
+        ),
+        get_response::<BuildingResponse>(
+            client,
+            format!("/nft/character/building?transportID={transport_id}&languageCode={language_code}")
+        ),
+        get_response::<HolyStuffResponse>(
+            client,
+            format!("/nft/character/holystuff?transportID={transport_id}&languageCode={language_code}")
+        ),
+        get_response::<InventoryResponse>(
+            client,
+            format!("/nft/character/inven?transportID={transport_id}&languageCode={language_code}")
+        ),
+    )?;
+
+    let mut names = NamesById::new();
+    for stat in stats.data.lists {
+        names.insert(("stat", stat.icon_path), stat.stat_name);
+    }
+    let mut skill_levels = Vec::new();
+    for (index, skill) in skills.data.into_iter().enumerate() {
+        names.insert(("skill", format!("{class}:{index}")), skill.skill_name);
+        skill_levels.push(skill.skill_level);
+    }
+    for (id, building) in buildings.data {
+        names.insert(("building", id), building.building_name);
+    }
+    for (id, holy_stuff) in holy_stuff.data {
+        names.insert(("holy_stuff", id), holy_stuff.holy_stuff_name);
+    }
+    for item in inventory.inventory {
+        names.insert(("item", item.item_id), item.item_name);
+    }
+
+    Ok(CharacterNames {
+        names,
+        skill_levels,
+    })
+}
+
+/// English names of a character, fetched once and matched against every other language
+pub async fn get_english_names(
+    transport_id: i32,
+    class: i32,
+    client: &ClientWithMiddleware,
+) -> anyhow::Result<CharacterNames> {
+    get_names(transport_id, class, client, "en").await
+}
+
+pub async fn get_localized_names(
+    transport_id: i32,
+    class: i32,
+    client: &ClientWithMiddleware,
+    english_names: &CharacterNames,
+    language_code: &str,
+) -> anyhow::Result<Vec<LocalizedName>> {
+    let localized_names = get_names(transport_id, class, client, language_code).await?;
+
+    let skills_match = localized_names.skill_levels == english_names.skill_levels;
+    if !skills_match {
+        tracing::warn!(
+            "Skills of {} in {} do not match the english ones, their names are skipped",
+            transport_id,
+            language_code
+        );
+    }
+
+    Ok(localized_names
+        .names
+        .into_iter()
+        .filter(|((kind, _), _)| skills_match || *kind != "skill")
+        .map(|((kind, key), name)| LocalizedName {
+            kind,
+            english_name: english_names.names.get(&(kind, key.clone())).cloned(),
+            key,
+            name,
+        })
+        .collect())
+}
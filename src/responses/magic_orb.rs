@@ -2,6 +2,7 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::error::CrawlError;
 use crate::utils::get_response;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -46,13 +47,13 @@ pub struct MagicOrb {
 pub async fn get_nft_magic_orb(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<MagicOrbResponse> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/magicorb?transportID={transport_id}&languageCode=en",
+) -> Result<MagicOrbResponse, CrawlError> {
+    let request_path = format!(
+        "/nft/character/magicorb?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: MagicOrbResponse = get_response(&client, request_url).await?;
+    let response_json: MagicOrbResponse = get_response(&client, request_path).await?;
 
     Ok(response_json)
 }
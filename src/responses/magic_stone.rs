@@ -1,12 +1,15 @@
+use crate::error::CrawlError;
+use crate::tradable::TradableCatalog;
 use crate::utils::get_response;
 
 use super::{
     inventory::InventoryItem,
-    item_detail::{get_item_detail, ItemDetail, ItemDetailAdd},
+    item_detail::{ItemDetail, ItemDetailAdd, ItemDetailCache},
 };
 
 use crate::utils::default_bool;
 
+use futures::future::join_all;
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -26,13 +29,6 @@ pub struct MagicStoneResponseObject {
     pub active_deck: i16,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum EquipItem {
-    HashMap(HashMap<String, HashMap<String, MagicStone>>),
-    EmptyArray(Vec<()>),
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all(serialize = "snake_case", deserialize = "snake_case"))]
 pub struct MagicStone {
@@ -63,51 +59,51 @@ pub async fn get_nft_magic_stone(
     class: i32,
     client: ClientWithMiddleware,
     inventory: Vec<InventoryItem>,
-    tradable_list: serde_json::Value,
-) -> anyhow::Result<MagicStoneResponseObject> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/magicstone?transportID={transport_id}&languageCode=en",
+    tradable: &TradableCatalog,
+    item_details: &ItemDetailCache,
+) -> Result<MagicStoneResponseObject, CrawlError> {
+    let request_path = format!(
+        "/nft/character/magicstone?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: MagicStoneResponse = get_response(&client, request_url).await?;
+    let response_json: MagicStoneResponse = get_response(&client, request_path).await?;
 
-    let mut magic_stones_decks: HashMap<String, HashMap<String, MagicStone>> = HashMap::new();
-    for (set_index, inner_hashmap) in response_json.data.equip_item.clone().into_iter() {
-        let mut magic_stones: HashMap<String, MagicStone> = HashMap::new();
-        for (slot_index, mut stone_value) in inner_hashmap.clone().into_iter() {
+    // every slot of every deck is looked up at the same time, bounded by the item detail cache
+    let (client, inventory) = (&client, &inventory);
+    let equip_item = response_json.data.equip_item.clone();
+    let decks = equip_item.into_iter().map(|(set_index, inner_hashmap)| async move {
+        let magic_stones = inner_hashmap.into_iter().map(|(slot_index, mut stone_value)| async move {
             let item_match = inventory
                 .iter()
                 .find(|inventory_item| inventory_item.item_id == stone_value.item_idx);
 
-            if item_match.is_some() {
-                let item_detail = get_item_detail(
-                    &client,
-                    &transport_id,
-                    &class,
-                    &item_match.unwrap().item_uid,
-                )
-                .await
-                .expect("Magic stone item detail failed");
-
+            if let Some(item) = item_match {
+                // a failed item detail fails the section, so it is marked missing and retried
+                let item_detail = item_details.get(client, transport_id, class, &item.item_uid).await?;
                 stone_value.options = item_detail.options;
                 stone_value.add_option = item_detail.add_option;
                 stone_value.power_score = item_detail.power_score;
 
-                if tradable_list[&stone_value.item_idx] == 1 {
+                if tradable.is_tradable(&stone_value.item_idx) {
                     stone_value.is_tradable = true
                 }
             } else {
-                println!("Inventory magic stone item match not found");
+                tracing::warn!("Inventory magic stone item match not found");
                 stone_value.options = Vec::new();
                 stone_value.add_option = Vec::new();
                 stone_value.power_score = 0;
             }
 
-            magic_stones.insert(slot_index, stone_value);
-        }
-        magic_stones_decks.insert(set_index, magic_stones);
-    }
+            Ok((slot_index, stone_value))
+        });
+        let slots = join_all(magic_stones).await.into_iter().collect::<Result<_, CrawlError>>()?;
+        Ok((set_index, slots))
+    });
+    let magic_stones_decks: HashMap<String, HashMap<String, MagicStone>> = join_all(decks)
+        .await
+        .into_iter()
+        .collect::<Result<_, CrawlError>>()?;
 
     let magic_stone_result = MagicStoneResponseObject {
         equip_item: magic_stones_decks,
@@ -1,9 +1,11 @@
 pub mod assets;
 pub mod building;
 pub mod codex;
+pub mod envelope;
 pub mod holy_stuff;
 pub mod inventory;
 pub mod item_detail;
+pub mod localization;
 pub mod magic_orb;
 pub mod magic_stone;
 pub mod mystical_piece;
@@ -1,13 +1,16 @@
+use futures::future::join_all;
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::utils::default_bool;
+use crate::error::CrawlError;
+use crate::tradable::TradableCatalog;
 use crate::utils::get_response;
 
 use super::{
     inventory::InventoryItem,
-    item_detail::{get_item_detail, ItemDetail, ItemDetailAdd},
+    item_detail::{ItemDetail, ItemDetailAdd, ItemDetailCache},
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -55,51 +58,51 @@ pub async fn get_nft_mystical_piece(
     class: i32,
     client: ClientWithMiddleware,
     inventory: Vec<InventoryItem>,
-    tradable_list: serde_json::Value,
-) -> anyhow::Result<MysticalPieceResponseObject> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/mysticalpiece?transportID={transport_id}&languageCode=en",
+    tradable: &TradableCatalog,
+    item_details: &ItemDetailCache,
+) -> Result<MysticalPieceResponseObject, CrawlError> {
+    let request_path = format!(
+        "/nft/character/mysticalpiece?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: MysticalPieceResponse = get_response(&client, request_url).await?;
+    let response_json: MysticalPieceResponse = get_response(&client, request_path).await?;
 
-    let mut mystical_pieces_decks: HashMap<String, HashMap<String, MysticalPiece>> = HashMap::new();
-    for (set_index, inner_hashmap) in response_json.data.equip_item.clone().into_iter() {
-        let mut mystical_pieces: HashMap<String, MysticalPiece> = HashMap::new();
-        for (slot_index, mut piece_value) in inner_hashmap.clone().into_iter() {
+    // every slot of every deck is looked up at the same time, bounded by the item detail cache
+    let (client, inventory) = (&client, &inventory);
+    let equip_item = response_json.data.equip_item.clone();
+    let decks = equip_item.into_iter().map(|(set_index, inner_hashmap)| async move {
+        let mystical_pieces = inner_hashmap.into_iter().map(|(slot_index, mut piece_value)| async move {
             let item_match = inventory
                 .iter()
                 .find(|inventory_item| inventory_item.item_id == piece_value.item_idx);
 
-            if item_match.is_some() {
-                let item_detail = get_item_detail(
-                    &client,
-                    &transport_id,
-                    &class,
-                    &item_match.unwrap().item_uid,
-                )
-                .await
-                .expect("Mystical piece item detail failed");
-
+            if let Some(item) = item_match {
+                // a failed item detail fails the section, so it is marked missing and retried
+                let item_detail = item_details.get(client, transport_id, class, &item.item_uid).await?;
                 piece_value.options = item_detail.options;
                 piece_value.add_option = item_detail.add_option;
                 piece_value.power_score = item_detail.power_score;
 
-                if tradable_list[&piece_value.item_idx] == 1 {
+                if tradable.is_tradable(&piece_value.item_idx) {
                     piece_value.is_tradable = true
                 }
             } else {
-                println!("Inventory mystical piece item match not found");
+                tracing::warn!("Inventory mystical piece item match not found");
                 piece_value.options = Vec::new();
                 piece_value.add_option = Vec::new();
                 piece_value.power_score = 0;
             }
 
-            mystical_pieces.insert(slot_index, piece_value);
-        }
-        mystical_pieces_decks.insert(set_index, mystical_pieces);
-    }
+            Ok((slot_index, piece_value))
+        });
+        let slots = join_all(mystical_pieces).await.into_iter().collect::<Result<_, CrawlError>>()?;
+        Ok((set_index, slots))
+    });
+    let mystical_pieces_decks: HashMap<String, HashMap<String, MysticalPiece>> = join_all(decks)
+        .await
+        .into_iter()
+        .collect::<Result<_, CrawlError>>()?;
 
     let mystical_piece_result = MysticalPieceResponseObject {
         equip_item: mystical_pieces_decks,
@@ -66,15 +66,18 @@ pub struct Nft {
     #[serde(default)]
     pub tickets: HashMap<String, i32>,
     #[serde(default)]
-    pub inventory_id: i64,
+    pub inventory_id: Option<i64>,
     #[serde(default)]
-    pub succession_id: i64,
+    pub succession_id: Option<i64>,
     #[serde(default)]
-    pub spirits_id: i64,
+    pub spirits_id: Option<i64>,
     #[serde(default)]
-    pub magic_orb_id: i64,
+    pub magic_orb_id: Option<i64>,
     #[serde(default)]
-    pub magic_stone_id: i64,
+    pub magic_stone_id: Option<i64>,
     #[serde(default)]
-    pub mystical_piece_id: i64,
+    pub mystical_piece_id: Option<i64>,
+    /// Sections that could not be fetched, the character is stored without them
+    #[serde(default)]
+    pub missing_sections: Vec<String>,
 }
@@ -1,6 +1,7 @@
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 
+use crate::error::CrawlError;
 use crate::utils::get_response;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -27,13 +28,13 @@ pub struct Potentials {
 pub async fn get_nft_potentials(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<Potentials> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/potential?transportID={transport_id}&languageCode=en",
+) -> Result<Potentials, CrawlError> {
+    let request_path = format!(
+        "/nft/character/potential?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: PotentialsResponse = get_response(&client, request_url).await?;
+    let response_json: PotentialsResponse = get_response(&client, request_path).await?;
 
     Ok(response_json.data)
 }
@@ -2,7 +2,8 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::utils::get_response;
+use crate::error::CrawlError;
+use crate::utils::get_response_with_body;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SkillsResponse {
@@ -17,33 +18,31 @@ pub struct SkillObject {
     pub skill_name: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Skills {
-    pub skills: HashMap<String, String>,
-}
-
 pub async fn get_nft_skills(
     transport_id: i32,
     character_class: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<HashMap<String, i32>> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/skills?transportID={transport_id}&class={character_class}&languageCode=en",
+) -> Result<HashMap<String, i32>, CrawlError> {
+    let request_path = format!(
+        "/nft/character/skills?transportID={transport_id}&class={character_class}&languageCode=en",
         transport_id = transport_id,
         character_class = character_class
     );
 
-    let response_json: SkillsResponse = get_response(&client, request_url).await?;
+    let (response_json, body): (SkillsResponse, String) = get_response_with_body(&client, request_path).await?;
 
     let skills_hashmap: HashMap<String, i32> = response_json
         .data
         .iter()
         .map(|skill_object| {
-            let value_as_number = skill_object.skill_level.parse::<i32>().unwrap();
+            let value_as_number = skill_object
+                .skill_level
+                .parse::<i32>()
+                .map_err(|error| CrawlError::decode(format!("{}: {:?}", error, skill_object.skill_level), &body))?;
 
-            (skill_object.skill_name.clone(), value_as_number)
+            Ok((skill_object.skill_name.clone(), value_as_number))
         })
-        .collect();
+        .collect::<Result<_, CrawlError>>()?;
 
     Ok(skills_hashmap)
 }
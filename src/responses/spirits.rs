@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 
+use crate::error::CrawlError;
 use crate::utils::get_response;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -32,13 +33,13 @@ pub struct Spirit {
 pub async fn get_nft_spirits(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<SpiritsObject> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/spirit?transportID={transport_id}&languageCode=en",
+) -> Result<SpiritsObject, CrawlError> {
+    let request_path = format!(
+        "/nft/character/spirit?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: SpiritsResponse = get_response(&client, request_url).await?;
+    let response_json: SpiritsResponse = get_response(&client, request_path).await?;
 
     Ok(response_json.data)
 }
@@ -3,7 +3,8 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::utils::get_response;
+use crate::error::CrawlError;
+use crate::utils::get_response_with_body;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StatsResponse {
@@ -28,13 +29,13 @@ pub struct Stats {
 pub async fn get_nft_stats(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<HashMap<String, f32>> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/stats?transportID={transport_id}&languageCode=en",
+) -> Result<HashMap<String, f32>, CrawlError> {
+    let request_path = format!(
+        "/nft/character/stats?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: StatsResponse = get_response(&client, request_url).await?;
+    let (response_json, body): (StatsResponse, String) = get_response_with_body(&client, request_path).await?;
 
     let re = Regex::new(r"%|,|sec").unwrap();
     let stats_hashmap: HashMap<String, f32> = response_json
@@ -43,11 +44,13 @@ pub async fn get_nft_stats(
         .iter()
         .map(|stats_object| {
             let parsed_value = re.replace_all(stats_object.stat_value.as_str(), "");
-            let value_as_number = parsed_value.into_owned().parse::<f32>().unwrap();
+            let value_as_number = parsed_value
+                .parse::<f32>()
+                .map_err(|error| CrawlError::decode(format!("{}: {:?}", error, stats_object.stat_value), &body))?;
 
-            (stats_object.stat_name.clone(), value_as_number)
+            Ok((stats_object.stat_name.clone(), value_as_number))
         })
-        .collect();
+        .collect::<Result<_, CrawlError>>()?;
 
     Ok(stats_hashmap)
 }
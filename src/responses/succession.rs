@@ -1,12 +1,14 @@
+use futures::future::join_all;
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::error::CrawlError;
 use crate::utils::get_response;
 
 use super::{
     inventory::InventoryItem,
-    item_detail::{get_item_detail, ItemDetail, ItemDetailAdd},
+    item_detail::{ItemDetail, ItemDetailAdd, ItemDetailCache},
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -61,40 +63,44 @@ pub async fn get_nft_succession(
     client: ClientWithMiddleware,
     class: i32,
     inventory: Vec<InventoryItem>,
-) -> anyhow::Result<SuccessionResponse> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/succession?transportID={transport_id}&languageCode=en",
+    item_details: &ItemDetailCache,
+) -> Result<SuccessionResponse, CrawlError> {
+    let request_path = format!(
+        "/nft/character/succession?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let mut response_json: SuccessionResponse = get_response(&client, request_url).await?;
+    let mut response_json: SuccessionResponse = get_response(&client, request_path).await?;
 
     match response_json.clone().data.equip_item {
         EquipItem::HashMap(item) => {
-            let mut succession_items: HashMap<String, SuccessionObject> = HashMap::new();
-            for (item_index, mut succession) in item.clone().into_iter() {
+            let (client, inventory) = (&client, &inventory);
+            let succession_items = item.into_iter().map(|(item_index, mut succession)| async move {
                 let item_match = inventory
                     .iter()
                     .find(|inventory_item| inventory_item.item_id == succession.item_idx);
 
                 if let Some(item) = item_match {
-                    let item_detail =
-                        get_item_detail(&client, &transport_id, &class, &item.item_uid)
-                            .await
-                            .expect("Succession item detail failed");
+                    // a failed item detail fails the section, so it is marked missing and retried
+                    let item_detail = item_details.get(client, transport_id, class, &item.item_uid).await?;
                     succession.options = item_detail.options;
                     succession.add_option = item_detail.add_option;
                     succession.power_score = item_detail.power_score;
                 } else {
-                    println!("Inventory succession item match not found");
+                    tracing::warn!("Inventory succession item match not found");
                     succession.options = Vec::new();
                     succession.add_option = Vec::new();
                     succession.power_score = 0;
                 }
 
-                succession_items.insert(item_index, succession);
-            }
-            response_json.data.equip_item = EquipItem::HashMap(succession_items);
+                Ok((item_index, succession))
+            });
+            response_json.data.equip_item = EquipItem::HashMap(
+                join_all(succession_items)
+                    .await
+                    .into_iter()
+                    .collect::<Result<_, CrawlError>>()?,
+            );
         }
         EquipItem::EmptyArray(_) => {}
     }
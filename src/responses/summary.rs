@@ -1,8 +1,11 @@
 use crate::{
-    responses::item_detail::{get_item_detail, ItemDetail},
+    error::CrawlError,
+    responses::item_detail::{ItemDetail, ItemDetailCache, ItemDetailData},
+    tradable::TradableCatalog,
     utils::default_bool,
-    utils::get_response,
+    utils::get_response_with_body,
 };
+use futures::future::join_all;
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -66,45 +69,49 @@ pub async fn get_nft_summary(
     class: i32,
     client: ClientWithMiddleware,
     inventory: Vec<InventoryItem>,
-    tradable_list: serde_json::Value,
-) -> anyhow::Result<SummaryReturnObject> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/summary?seq={seq}&languageCode=en",
+    tradable: &TradableCatalog,
+    item_details: &ItemDetailCache,
+) -> Result<SummaryReturnObject, CrawlError> {
+    let request_path = format!(
+        "/nft/character/summary?seq={seq}&languageCode=en",
         seq = seq
     );
 
-    let mut response_json: SummaryResponse = get_response(&client, request_url).await?;
+    let (response_json, body): (SummaryResponse, String) =
+        get_response_with_body(&client, request_path).await?;
 
-    let mut equip_items: HashMap<String, EquipItem> = HashMap::new();
-    for (key, value) in response_json.data.equip_items.clone().into_iter() {
+    let (client, inventory, body) = (&client, &inventory, &body);
+    let equip_items = response_json.data.equip_items.into_iter().map(|(key, mut equip_item)| async move {
+        let item_id = equip_item["itemIdx"].as_str().unwrap_or_default().to_string();
         let item_match = inventory
             .iter()
-            .find(|inventory_item| inventory_item.item_id == value["itemIdx"])
-            .expect("Item not found in inventory.");
-        let item_detail = get_item_detail(&client, &transport_id, &class, &item_match.item_uid)
-            .await
-            .expect("item detail failed");
+            .find(|inventory_item| inventory_item.item_id == item_id);
 
-        response_json
-            .data
-            .equip_items
-            .entry(key.clone())
-            .and_modify(|equip_item| {
-                equip_item["options"] = serde_json::to_value(item_detail.options).unwrap();
-                equip_item["add_option"] = serde_json::to_value(item_detail.add_option).unwrap();
-                equip_item["power_score"] = serde_json::to_value(item_detail.power_score).unwrap();
+        // a failed item detail fails the section, so it is marked missing and retried
+        let item_detail = match item_match {
+            Some(item) => item_details.get(client, transport_id, class, &item.item_uid).await?,
+            None => {
+                tracing::warn!("Equipped item {} not found in inventory", item_id);
+                ItemDetailData::default()
+            }
+        };
 
-                let item_id: String =
-                    serde_json::from_value(equip_item["itemIdx"].clone()).unwrap();
-                if tradable_list[&item_id.to_string()] == 1 {
-                    equip_item["is_tradable"] = serde_json::to_value(true).unwrap();
-                }
-            });
-        let equip_object: EquipItem =
-            serde_json::from_value(response_json.data.equip_items[&key].clone())?;
+        equip_item["options"] = serde_json::json!(item_detail.options);
+        equip_item["add_option"] = serde_json::json!(item_detail.add_option);
+        equip_item["power_score"] = serde_json::json!(item_detail.power_score);
+        if tradable.is_tradable(&item_id) {
+            equip_item["is_tradable"] = serde_json::json!(true);
+        }
 
-        equip_items.insert(key, equip_object);
-    }
+        let equip_object: EquipItem = serde_json::from_value(equip_item)
+            .map_err(|error| CrawlError::decode(format!("equipped item {}: {}", key, error), body))?;
+
+        Ok((key, equip_object))
+    });
+    let equip_items: HashMap<String, EquipItem> = join_all(equip_items)
+        .await
+        .into_iter()
+        .collect::<Result<_, CrawlError>>()?;
 
     let summary_to_db: SummaryReturnObject = SummaryReturnObject {
         trade_type: response_json.data.trade_type,
@@ -1,10 +1,11 @@
 use std::collections::HashMap;
 
 use super::inventory::InventoryItem;
+use crate::error::CrawlError;
 
 pub async fn get_nft_tickets(
     inventory: Vec<InventoryItem>,
-) -> anyhow::Result<HashMap<String, i32>> {
+) -> Result<HashMap<String, i32>, CrawlError> {
     let mut tickets: HashMap<String, i32> = HashMap::new();
 
     inventory.iter().for_each(|x| match x.item_name.as_str() {
@@ -2,13 +2,13 @@ use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::utils::get_response;
+use crate::error::CrawlError;
+use crate::utils::get_response_with_body;
 
 use super::codex::StringOrI32;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TrainingResponse {
-    pub code: u16,
     pub data: TrainingResponseData,
 }
 
@@ -49,39 +49,40 @@ pub struct TrainingObject {
 pub async fn get_nft_training(
     transport_id: i32,
     client: ClientWithMiddleware,
-) -> anyhow::Result<HashMap<String, StringOrI32>> {
-    let request_url = format!(
-        "https://webapi.mir4global.com/nft/character/training?transportID={transport_id}&languageCode=en",
+) -> Result<HashMap<String, StringOrI32>, CrawlError> {
+    let request_path = format!(
+        "/nft/character/training?transportID={transport_id}&languageCode=en",
         transport_id = transport_id
     );
 
-    let response_json: TrainingResponse = get_response(&client, request_url).await?;
+    let (response_json, body): (TrainingResponse, String) =
+        get_response_with_body(&client, request_path).await?;
     let data = response_json.data;
 
     let training_hashmap: HashMap<String, StringOrI32> = HashMap::from([
         (
             "Violet Mist Art".to_string(),
-            StringOrI32::Integer(data.violet_mist_art.force_level.as_i32().unwrap()),
+            StringOrI32::Integer(data.violet_mist_art.force_level.try_as_i32(&body)?),
         ),
         (
             "Muscle Strength Manual".to_string(),
-            StringOrI32::Integer(data.muscle_strength_manual.force_level.as_i32().unwrap()),
+            StringOrI32::Integer(data.muscle_strength_manual.force_level.try_as_i32(&body)?),
         ),
         (
             "Nine Yang Manual".to_string(),
-            StringOrI32::Integer(data.nine_yang_manual.force_level.as_i32().unwrap()),
+            StringOrI32::Integer(data.nine_yang_manual.force_level.try_as_i32(&body)?),
         ),
         (
             "Toad Stance".to_string(),
-            StringOrI32::Integer(data.toad_stance.force_level.as_i32().unwrap()),
+            StringOrI32::Integer(data.toad_stance.force_level.try_as_i32(&body)?),
         ),
         (
             "Northern Profound Art".to_string(),
-            StringOrI32::Integer(data.northern_profound_art.force_level.as_i32().unwrap()),
+            StringOrI32::Integer(data.northern_profound_art.force_level.try_as_i32(&body)?),
         ),
         (
             "Nine Yin Manual".to_string(),
-            StringOrI32::Integer(data.nine_yin_manual.force_level.as_i32().unwrap()),
+            StringOrI32::Integer(data.nine_yin_manual.force_level.try_as_i32(&body)?),
         ),
         ("Constitution".to_string(), data.consitution_level),
         ("collect_name".to_string(), data.collect_name),
@@ -0,0 +1,229 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+use tower_http::cors::CorsLayer;
+
+const DEFAULT_PER_PAGE: i64 = 20;
+const MAX_PER_PAGE: i64 = 100;
+
+// nft row merged with every child document it references
+const NFT_DETAIL_QUERY: &str = r#"
+    SELECT to_jsonb(n) || jsonb_build_object(
+        'inventory', to_jsonb(i) - 'id',
+        'succession', s.succession,
+        'spirits', to_jsonb(sp) - 'id',
+        'magic_orb', to_jsonb(mo) - 'id',
+        'magic_stone', to_jsonb(ms) - 'id',
+        'mystical_piece', to_jsonb(mp) - 'id'
+    )
+    FROM nft n
+    LEFT JOIN inventory i ON i.id = n.inventory_id
+    LEFT JOIN succession s ON s.id = n.succession_id
+    LEFT JOIN spirits sp ON sp.id = n.spirits_id
+    LEFT JOIN magic_orb mo ON mo.id = n.magic_orb_id
+    LEFT JOIN magic_stone ms ON ms.id = n.magic_stone_id
+    LEFT JOIN mystical_piece mp ON mp.id = n.mystical_piece_id
+"#;
+
+#[derive(Deserialize, Debug)]
+pub struct Pagination {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub status: Option<String>,
+}
+
+pub enum ApiError {
+    NotFound,
+    BadRequest(&'static str),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        ApiError::Database(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::NotFound => {
+                (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "not found" })))
+                    .into_response()
+            }
+            ApiError::BadRequest(message) => {
+                (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": message })))
+                    .into_response()
+            }
+            ApiError::Database(error) => {
+                tracing::error!("Database error while serving request: {:#?}", error);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({ "error": "internal server error" })),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+pub async fn serve(pool: Pool<Postgres>, port: u16) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .route("/nft", get(list_nft))
+        .route("/nft/transport_id/:transport_id", get(get_nft_by_transport_id))
+        .route(
+            "/nft/transport_id/:transport_id/price_history",
+            get(get_nft_price_history),
+        )
+        .route("/localized_names/:language_code", get(get_localized_names))
+        .route("/nft/seq/:seq", get(get_nft_by_seq))
+        .route("/nft/nft_id/:nft_id", get(get_nft_by_nft_id))
+        .route("/items/:item_id", get(get_item))
+        .layer(CorsLayer::permissive())
+        .with_state(pool);
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    tracing::info!("Listening on {}", listener.local_addr()?);
+
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+async fn list_nft(
+    State(pool): State<Pool<Postgres>>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let page = pagination.page.unwrap_or(1).max(1);
+    let per_page = pagination
+        .per_page
+        .unwrap_or(DEFAULT_PER_PAGE)
+        .clamp(1, MAX_PER_PAGE);
+    let offset = (page - 1)
+        .checked_mul(per_page)
+        .ok_or(ApiError::BadRequest("page is too large"))?;
+
+    let total: (i64,) =
+        sqlx::query_as("SELECT count(*) FROM nft WHERE $1::text IS NULL OR status = $1")
+            .bind(&pagination.status)
+            .fetch_one(&pool)
+            .await?;
+
+    let rows: Vec<(serde_json::Value,)> = sqlx::query_as(
+        "SELECT to_jsonb(nft) FROM nft WHERE $1::text IS NULL OR status = $1 ORDER BY id DESC LIMIT $2 OFFSET $3",
+    )
+    .bind(&pagination.status)
+    .bind(per_page)
+    .bind(offset)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(serde_json::json!({
+        "page": page,
+        "per_page": per_page,
+        "total": total.0,
+        "items": rows.into_iter().map(|row| row.0).collect::<Vec<_>>(),
+    })))
+}
+
+async fn get_nft_by_transport_id(
+    State(pool): State<Pool<Postgres>>,
+    Path(transport_id): Path<i64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let row: Option<(serde_json::Value,)> =
+        sqlx::query_as(&format!("{NFT_DETAIL_QUERY} WHERE n.transport_id = $1"))
+            .bind(transport_id)
+            .fetch_optional(&pool)
+            .await?;
+
+    row.map(|row| Json(row.0)).ok_or(ApiError::NotFound)
+}
+
+async fn get_nft_by_seq(
+    State(pool): State<Pool<Postgres>>,
+    Path(seq): Path<i32>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let row: Option<(serde_json::Value,)> =
+        sqlx::query_as(&format!("{NFT_DETAIL_QUERY} WHERE n.seq = $1"))
+            .bind(seq)
+            .fetch_optional(&pool)
+            .await?;
+
+    row.map(|row| Json(row.0)).ok_or(ApiError::NotFound)
+}
+
+async fn get_nft_by_nft_id(
+    State(pool): State<Pool<Postgres>>,
+    Path(nft_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let row: Option<(serde_json::Value,)> =
+        sqlx::query_as(&format!("{NFT_DETAIL_QUERY} WHERE n.nft_id = $1"))
+            .bind(nft_id)
+            .fetch_optional(&pool)
+            .await?;
+
+    row.map(|row| Json(row.0)).ok_or(ApiError::NotFound)
+}
+
+async fn get_nft_price_history(
+    State(pool): State<Pool<Postgres>>,
+    Path(transport_id): Path<i64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let rows: Vec<(serde_json::Value,)> = sqlx::query_as(
+        "SELECT to_jsonb(h) - 'id' FROM nft_price_history h WHERE transport_id = $1 ORDER BY observed_at",
+    )
+    .bind(transport_id)
+    .fetch_all(&pool)
+    .await?;
+
+    if rows.is_empty() {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(Json(serde_json::json!(rows
+        .into_iter()
+        .map(|row| row.0)
+        .collect::<Vec<_>>())))
+}
+
+async fn get_localized_names(
+    State(pool): State<Pool<Postgres>>,
+    Path(language_code): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let row: (Option<serde_json::Value>,) = sqlx::query_as(
+        r#"
+        SELECT jsonb_object_agg(kind, names)
+        FROM (
+            SELECT kind, jsonb_object_agg(key, jsonb_build_object('english_name', english_name, 'name', name)) AS names
+            FROM localized_name
+            WHERE language_code = $1
+            GROUP BY kind
+        ) AS localized
+        "#,
+    )
+    .bind(language_code)
+    .fetch_one(&pool)
+    .await?;
+
+    row.0.map(Json).ok_or(ApiError::NotFound)
+}
+
+async fn get_item(
+    State(pool): State<Pool<Postgres>>,
+    Path(item_id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let row: Option<(serde_json::Value,)> =
+        sqlx::query_as("SELECT to_jsonb(c) FROM item_catalog c WHERE item_id = $1")
+            .bind(item_id)
+            .fetch_optional(&pool)
+            .await?;
+
+    row.map(|row| Json(row.0)).ok_or(ApiError::NotFound)
+}
@@ -0,0 +1,191 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::Extensions;
+use reqwest::{Request, Response, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+// spacing used when the api pushes back while no requests per second limit is set
+const MIN_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+struct Pacing {
+    /// Spacing given by --requests-per-second
+    base_interval: Duration,
+    /// Current spacing, grows on 429/5xx and shrinks back to `base_interval` on success
+    interval: Duration,
+    /// When the next request may be sent
+    next_slot: Instant,
+    /// Last time `interval` grew, the answers to a burst of requests only slow down once
+    slowed_at: Option<Instant>,
+}
+
+/// Limits the requests in flight and the requests per second sent to the Mir4 API, slowing down
+/// when it answers 429 or 5xx
+#[derive(Clone)]
+pub struct RateLimiter {
+    permits: Arc<Semaphore>,
+    pacing: Arc<Mutex<Pacing>>,
+}
+
+impl RateLimiter {
+    /// A `requests_per_second` of 0 only limits the requests in flight
+    pub fn new(max_in_flight: usize, requests_per_second: u32) -> Self {
+        let base_interval = match requests_per_second {
+            0 => Duration::ZERO,
+            requests_per_second => Duration::from_secs(1) / requests_per_second,
+        };
+
+        RateLimiter {
+            permits: Arc::new(Semaphore::new(max_in_flight.max(1))),
+            pacing: Arc::new(Mutex::new(Pacing {
+                base_interval,
+                interval: base_interval,
+                next_slot: Instant::now(),
+                slowed_at: None,
+            })),
+        }
+    }
+
+    fn reserve_slot(&self) -> Instant {
+        let mut pacing = self.pacing.lock().unwrap();
+        let slot = pacing.next_slot.max(Instant::now());
+        pacing.next_slot = slot + pacing.interval;
+
+        slot
+    }
+
+    fn slow_down(&self, status: StatusCode, retry_after: Option<Duration>) {
+        let mut pacing = self.pacing.lock().unwrap();
+        let now = Instant::now();
+        if let Some(retry_after) = retry_after {
+            pacing.next_slot = pacing.next_slot.max(now + retry_after.min(MAX_BACKOFF));
+        }
+        if pacing.slowed_at.is_some_and(|slowed_at| now - slowed_at < pacing.interval) {
+            return;
+        }
+
+        pacing.interval = (pacing.interval * 2).clamp(MIN_BACKOFF, MAX_BACKOFF);
+        pacing.slowed_at = Some(now);
+        tracing::warn!(
+            "Mir4 API answered {}, sending at most one request every {:?}",
+            status,
+            pacing.interval
+        );
+    }
+
+    fn speed_up(&self) {
+        let mut pacing = self.pacing.lock().unwrap();
+        if pacing.interval > pacing.base_interval {
+            pacing.interval = (pacing.interval - pacing.interval / 10).max(pacing.base_interval);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for RateLimiter {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let _permit = self.permits.acquire().await.expect("semaphore is never closed");
+        tokio::time::sleep_until(self.reserve_slot()).await;
+
+        let response = next.run(req, extensions).await?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
+            self.slow_down(status, retry_after);
+        } else {
+            self.speed_up();
+        }
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(limiter: &RateLimiter) -> Duration {
+        limiter.pacing.lock().unwrap().interval
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spaces_requests_by_the_interval() {
+        let limiter = RateLimiter::new(4, 10);
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve_slot(), now);
+        assert_eq!(limiter.reserve_slot(), now + Duration::from_millis(100));
+        assert_eq!(limiter.reserve_slot(), now + Duration::from_millis(200));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn doubles_the_interval_once_per_burst() {
+        let limiter = RateLimiter::new(4, 10);
+
+        limiter.slow_down(StatusCode::TOO_MANY_REQUESTS, None);
+        assert_eq!(interval(&limiter), Duration::from_millis(200));
+
+        // answers to requests sent before the first slow down
+        limiter.slow_down(StatusCode::TOO_MANY_REQUESTS, None);
+        assert_eq!(interval(&limiter), Duration::from_millis(200));
+
+        tokio::time::advance(Duration::from_millis(200)).await;
+        limiter.slow_down(StatusCode::SERVICE_UNAVAILABLE, None);
+        assert_eq!(interval(&limiter), Duration::from_millis(400));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slows_down_without_a_requests_per_second_limit() {
+        let limiter = RateLimiter::new(4, 0);
+
+        limiter.slow_down(StatusCode::TOO_MANY_REQUESTS, None);
+        assert_eq!(interval(&limiter), MIN_BACKOFF);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn never_waits_more_than_max_backoff() {
+        let limiter = RateLimiter::new(4, 1);
+
+        for _ in 0..10 {
+            limiter.slow_down(StatusCode::TOO_MANY_REQUESTS, None);
+            tokio::time::advance(MAX_BACKOFF).await;
+        }
+        assert_eq!(interval(&limiter), MAX_BACKOFF);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_retry_after() {
+        let limiter = RateLimiter::new(4, 10);
+
+        limiter.slow_down(StatusCode::TOO_MANY_REQUESTS, Some(Duration::from_secs(5)));
+        assert_eq!(limiter.reserve_slot(), Instant::now() + Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn recovers_the_base_interval_on_success() {
+        let limiter = RateLimiter::new(4, 10);
+        limiter.slow_down(StatusCode::TOO_MANY_REQUESTS, None);
+
+        limiter.speed_up();
+        assert_eq!(interval(&limiter), Duration::from_millis(180));
+
+        for _ in 0..20 {
+            limiter.speed_up();
+        }
+        assert_eq!(interval(&limiter), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// TradeType of an item in the game's ITEM.json
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeType {
+    /// 0, bound to the character
+    Untradable,
+    /// 1, can be traded on its own
+    Tradable,
+    /// A value the game data did not use when this was written
+    Other(i64),
+}
+
+impl From<i64> for TradeType {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => TradeType::Untradable,
+            1 => TradeType::Tradable,
+            other => TradeType::Other(other),
+        }
+    }
+}
+
+/// Trade type of every item id, loaded once from list.json and shared by every crawl task
+pub struct TradableCatalog {
+    trade_types: HashMap<String, TradeType>,
+    unknown: Mutex<BTreeSet<String>>,
+}
+
+impl TradableCatalog {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = fs::read_to_string(path).map_err(|error| {
+            anyhow::anyhow!(
+                "could not read the tradable item list {}, build it with the catalog command: {}",
+                path.display(),
+                error
+            )
+        })?;
+        let list: HashMap<String, serde_json::Value> = serde_json::from_str(&data)?;
+
+        // items without a TradeType were never tradable, older lists kept it as a string
+        let trade_types = list
+            .into_iter()
+            .map(|(item_id, trade_type)| {
+                let trade_type = dump_trade_items::integer(&trade_type).unwrap_or(0);
+                (item_id, TradeType::from(trade_type))
+            })
+            .collect();
+
+        Ok(TradableCatalog {
+            trade_types,
+            unknown: Mutex::new(BTreeSet::new()),
+        })
+    }
+
+    /// Trade type of an item, ids missing from list.json are remembered for `unknown_items`
+    pub fn trade_type(&self, item_id: &str) -> Option<TradeType> {
+        let trade_type = self.trade_types.get(item_id).copied();
+        if trade_type.is_none() && self.unknown.lock().unwrap().insert(item_id.to_string()) {
+            tracing::warn!("Item {} is not in the tradable item list", item_id);
+        }
+
+        trade_type
+    }
+
+    pub fn is_tradable(&self, item_id: &str) -> bool {
+        self.trade_type(item_id) == Some(TradeType::Tradable)
+    }
+
+    /// Item ids seen while crawling that list.json does not have, an outdated list.json
+    pub fn unknown_items(&self) -> Vec<String> {
+        self.unknown.lock().unwrap().iter().cloned().collect()
+    }
+}
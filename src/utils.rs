@@ -1,10 +1,37 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
 use reqwest_middleware::ClientWithMiddleware;
 use serde::de::DeserializeOwned;
 use sqlx::{Pool, Postgres};
 
+use crate::error::CrawlError;
+use crate::metrics::RequestCounter;
+use crate::ndjson::NdjsonWriter;
+use crate::responses::envelope::ApiEnvelope;
+use crate::responses::item_detail::ItemDetailCache;
+use crate::tradable::TradableCatalog;
+
 static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
+pub const DEFAULT_API_BASE_URL: &str = "https://webapi.mir4global.com";
+
+static API_BASE_URL: OnceLock<String> = OnceLock::new();
+
+/// Sets the Mir4 API base URL every request path is resolved against. Only the first call has effect.
+pub fn set_api_base_url(base_url: &str) {
+    let _ = API_BASE_URL.set(base_url.trim_end_matches('/').to_string());
+}
+
+pub fn api_base_url() -> &'static str {
+    API_BASE_URL
+        .get()
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_API_BASE_URL)
+}
+
 #[allow(unused)]
 pub fn print_type_of<T>(_: &T) {
     tracing::info!("{}", std::any::type_name::<T>())
@@ -12,20 +39,45 @@ pub fn print_type_of<T>(_: &T) {
 
 pub async fn get_response<T>(
     client: &ClientWithMiddleware,
-    request_url: String,
-) -> anyhow::Result<T>
+    request_path: String,
+) -> Result<T, CrawlError>
+where
+    T: DeserializeOwned,
+{
+    Ok(get_response_with_body(client, request_path).await?.0)
+}
+
+/// Like `get_response`, also returning the raw body for the errors found while using the answer
+pub async fn get_response_with_body<T>(
+    client: &ClientWithMiddleware,
+    request_path: String,
+) -> Result<(T, String), CrawlError>
 where
     T: DeserializeOwned,
 {
     let response = client
-        .get(request_url)
+        .get(format!("{}{}", api_base_url(), request_path))
         .header("User-Agent", APP_USER_AGENT)
         .send()
         .await?;
 
-    let body = response.text().await?;
-    let result = serde_json::from_str(&body)?;
-    Ok(result)
+    let body = response
+        .text()
+        .await
+        .map_err(reqwest_middleware::Error::from)?;
+
+    let envelope = ApiEnvelope::parse(&body)?;
+
+    Ok((envelope.body, body))
+}
+
+/// Opens `path` for writing, or stdout when it is `-`
+pub fn create_output(path: &Path) -> io::Result<Box<dyn Write + Send>> {
+    if path == Path::new("-") {
+        Ok(Box::new(io::stdout()))
+    } else {
+        Ok(Box::new(File::create(path)?))
+    }
 }
 
 pub fn nft_description_error(message: &str, nft_data: serde_json::Value) -> String {
@@ -35,9 +87,27 @@ pub fn nft_description_error(message: &str, nft_data: serde_json::Value) -> Stri
     )
 }
 
+#[derive(Clone)]
 pub struct AppState {
-    pub db: Pool<Postgres>,
+    /// Where characters are stored, dry runs have none
+    pub db: Option<Pool<Postgres>>,
+    /// Dry runs write every crawled character here, one JSON line each
+    pub ndjson: Option<NdjsonWriter>,
     pub client: ClientWithMiddleware,
+    pub tradable: Arc<TradableCatalog>,
+    pub item_details: ItemDetailCache,
+    /// Languages crawled for localized names, besides english
+    pub languages: Vec<String>,
+    pub requests: RequestCounter,
+}
+
+impl AppState {
+    /// Database of every run that is not a dry run
+    pub fn pool(&self) -> anyhow::Result<&Pool<Postgres>> {
+        self.db
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("this command needs a database"))
+    }
 }
 
 pub fn default_bool() -> bool {
@@ -47,3 +117,4 @@ pub fn default_bool() -> bool {
 pub fn default_hashmap() -> HashMap<String, i32> {
     HashMap::new()
 }
+